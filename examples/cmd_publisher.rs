@@ -1,4 +1,4 @@
-// Keyboard teleop: WASD move, Z/X rotate, R/F speed, Q quit
+// Keyboard teleop: WASD move, Z/X rotate, R/F speed, Space estop, Enter reset, Q quit
 use clap::Parser;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
@@ -46,13 +46,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     info!("Opening Zenoh session...");
     let session = zenoh::open(config).await?;
-    let publisher = session.declare_publisher("lekiwi/cmd/base").await?;
+    // Teleop source: overrides autonomy and scripts in the runtime's command mux
+    let publisher = session.declare_publisher("lekiwi/cmd/base/teleop").await?;
+    let estop = session.declare_publisher("lekiwi/cmd/estop").await?;
 
-    info!("Controls: WASD=move, Z/X=rotate, R/F=speed, Q=quit");
+    info!("Controls: WASD=move, Z/X=rotate, R/F=speed, Space=estop, Enter=reset estop, Q=quit");
     info!("Speed: LOW");
 
     enable_raw_mode()?;
-    let result = run_teleop(&publisher).await;
+    let result = run_teleop(&publisher, &estop).await;
     disable_raw_mode()?;
 
     result
@@ -60,6 +62,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
 async fn run_teleop(
     publisher: &zenoh::pubsub::Publisher<'_>,
+    estop: &zenoh::pubsub::Publisher<'_>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let mut speed_idx: usize = 0;
 
@@ -71,54 +74,64 @@ async fn run_teleop(
 
     loop {
         // Poll for key with 20ms timeout (50Hz effective rate)
-        if event::poll(Duration::from_millis(20))? {
-            if let Event::Key(KeyEvent { code, kind, .. }) = event::read()? {
-                let pressed = kind == KeyEventKind::Press || kind == KeyEventKind::Repeat;
-
-                match code {
-                    // Movement - update velocity and refresh timestamp
-                    KeyCode::Char('w') if pressed => {
-                        x_vel = SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-                    KeyCode::Char('s') if pressed => {
-                        x_vel = -SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-                    KeyCode::Char('a') if pressed => {
-                        y_vel = SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-                    KeyCode::Char('d') if pressed => {
-                        y_vel = -SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-
-                    // Rotation
-                    KeyCode::Char('z') if pressed => {
-                        theta_vel = THETA_SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-                    KeyCode::Char('x') if pressed => {
-                        theta_vel = -THETA_SPEEDS[speed_idx];
-                        last_movement_input = Instant::now();
-                    }
-
-                    // Speed control
-                    KeyCode::Char('r') if pressed => {
-                        speed_idx = (speed_idx + 1).min(2);
-                        print_speed(speed_idx);
-                    }
-                    KeyCode::Char('f') if pressed => {
-                        speed_idx = speed_idx.saturating_sub(1);
-                        print_speed(speed_idx);
-                    }
-
-                    // Quit
-                    KeyCode::Char('q') | KeyCode::Esc if pressed => break,
-
-                    _ => {}
+        if event::poll(Duration::from_millis(20))?
+            && let Event::Key(KeyEvent { code, kind, .. }) = event::read()?
+        {
+            let pressed = kind == KeyEventKind::Press || kind == KeyEventKind::Repeat;
+
+            match code {
+                // Movement - update velocity and refresh timestamp
+                KeyCode::Char('w') if pressed => {
+                    x_vel = SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
                 }
+                KeyCode::Char('s') if pressed => {
+                    x_vel = -SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
+                }
+                KeyCode::Char('a') if pressed => {
+                    y_vel = SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
+                }
+                KeyCode::Char('d') if pressed => {
+                    y_vel = -SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
+                }
+
+                // Rotation
+                KeyCode::Char('z') if pressed => {
+                    theta_vel = THETA_SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
+                }
+                KeyCode::Char('x') if pressed => {
+                    theta_vel = -THETA_SPEEDS[speed_idx];
+                    last_movement_input = Instant::now();
+                }
+
+                // Speed control
+                KeyCode::Char('r') if pressed => {
+                    speed_idx = (speed_idx + 1).min(2);
+                    print_speed(speed_idx);
+                }
+                KeyCode::Char('f') if pressed => {
+                    speed_idx = speed_idx.saturating_sub(1);
+                    print_speed(speed_idx);
+                }
+
+                // Emergency stop (latches in the runtime until reset)
+                KeyCode::Char(' ') if pressed => {
+                    estop.put(json!({"action": "engage"}).to_string()).await?;
+                    info!("ESTOP engaged (Enter to reset)");
+                }
+                KeyCode::Enter if pressed => {
+                    estop.put(json!({"action": "reset"}).to_string()).await?;
+                    info!("Estop reset");
+                }
+
+                // Quit
+                KeyCode::Char('q') | KeyCode::Esc if pressed => break,
+
+                _ => {}
             }
         }
 
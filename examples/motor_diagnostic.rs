@@ -3,10 +3,11 @@
 // This tool does NOT write anything to the motors - it's completely safe.
 // Use this first before running motor_test.
 //
-// Usage: cargo run --example motor_diagnostic -- [port]
+// Usage: cargo run --example motor_diagnostic -- [port | --sim]
 // Example: cargo run --example motor_diagnostic -- /dev/tty.usbmodem58760432781
 
 use lekiwi_zenoh_runtime::motor::feetech::{FeetechBus, Register};
+use lekiwi_zenoh_runtime::motor::sim::SimServoBank;
 use std::io::{self, Write};
 
 const MOTOR_IDS: [u8; 3] = [7, 8, 9];
@@ -21,10 +22,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .init();
 
-    // Get port from args or use default
+    // Get port from args or use default; `--sim` reads simulated servos instead
     let port = std::env::args()
         .nth(1)
         .unwrap_or_else(|| "/dev/tty.usbmodem58760432781".to_string());
+    let sim = port == "--sim";
 
     println!("╔══════════════════════════════════════════════════════════════╗");
     println!("║           LeKiwi Motor Diagnostic (READ-ONLY)                ║");
@@ -38,7 +40,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Try to open serial port
     println!("Step 1: Opening serial port...");
-    let mut bus = match FeetechBus::open(&port) {
+    let opened = if sim {
+        Ok(FeetechBus::new(Box::new(
+            SimServoBank::lekiwi().transport(),
+        )))
+    } else {
+        FeetechBus::open(&port)
+    };
+    let mut bus = match opened {
         Ok(bus) => {
             println!("  ✓ Serial port opened successfully");
             bus
@@ -101,7 +110,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("  === Motor {} (ID {}) ===", MOTOR_NAMES[i], id);
 
         // Try to read operating mode
-        match bus.read_u8(id, Register::OperatingMode) {
+        match bus.read(id, Register::OperatingMode) {
             Ok(mode) => {
                 let mode_str = match mode {
                     0 => "Position",
@@ -116,7 +125,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // Torque enable
-        match bus.read_u8(id, Register::TorqueEnable) {
+        match bus.read(id, Register::TorqueEnable) {
             Ok(val) => {
                 let status = if val == 1 { "ENABLED" } else { "disabled" };
                 println!("    Torque Enable:  {} ({})", val, status);
@@ -125,7 +134,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // Lock
-        match bus.read_u8(id, Register::Lock) {
+        match bus.read(id, Register::Lock) {
             Ok(val) => {
                 let status = if val == 1 { "LOCKED" } else { "unlocked" };
                 println!("    Lock:           {} ({})", val, status);
@@ -142,7 +151,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // Present position
-        match bus.read_u16(id, Register::PresentPosition) {
+        match bus.read(id, Register::PresentPosition) {
             Ok(pos) => {
                 let degrees = (pos as f32) * 360.0 / 4096.0;
                 println!("    Present Position: {} ({:.1}°)", pos, degrees);
@@ -150,6 +159,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Err(e) => println!("    Present Position: ERROR - {}", e),
         }
 
+        // Present load, voltage and temperature
+        match bus.read(id, Register::PresentLoad) {
+            Ok(load) => println!("    Present Load:   {:.1}%", load as f32 / 10.0),
+            Err(e) => println!("    Present Load:   ERROR - {}", e),
+        }
+        match bus.read(id, Register::PresentVoltage) {
+            Ok(v) => println!("    Voltage:        {:.1} V", v as f32 / 10.0),
+            Err(e) => println!("    Voltage:        ERROR - {}", e),
+        }
+        match bus.read(id, Register::PresentTemperature) {
+            Ok(t) => println!("    Temperature:    {} °C", t),
+            Err(e) => println!("    Temperature:    ERROR - {}", e),
+        }
+
         println!();
     }
 
@@ -12,7 +12,7 @@
 // - Easy abort with Ctrl+C
 
 use lekiwi_zenoh_runtime::motor::feetech::{FeetechBus, OperatingMode};
-use lekiwi_zenoh_runtime::motor::kinematics::{body_to_wheel_raw, WheelVelocities};
+use lekiwi_zenoh_runtime::motor::kinematics::{WheelVelocities, body_to_wheel_raw};
 use std::io::{self, Write};
 use std::thread::sleep;
 use std::time::Duration;
@@ -222,14 +222,14 @@ fn send_wheel_velocities(
     // Use sync_write for efficiency
     use lekiwi_zenoh_runtime::motor::feetech::Register;
 
-    // Encode sign-magnitude and send via sync_write
+    // The register map handles the sign-magnitude encoding
     let data = [
-        (MOTOR_IDS[0], vel.left),
-        (MOTOR_IDS[1], vel.back),
-        (MOTOR_IDS[2], vel.right),
+        (MOTOR_IDS[0], vel.left as i32),
+        (MOTOR_IDS[1], vel.back as i32),
+        (MOTOR_IDS[2], vel.right as i32),
     ];
 
-    bus.sync_write_i16(Register::GoalVelocity, &data)?;
+    bus.sync_write_register(Register::GoalVelocity, &data)?;
     Ok(())
 }
 
@@ -0,0 +1,289 @@
+// Motor tool: setup tasks for the servos on the LeKiwi bus
+//
+// Usage: cargo run --example motor_tool -- <command> [options]
+//
+//   scan        List every servo on the bus, at every supported baud rate
+//   calibrate   Record homing offsets, ranges and directions by moving the
+//               motors by hand, and save them to a calibration file
+//   set-id      Change the ID of the only servo connected
+//   set-baud    Change the baud rate of the only servo connected
+//
+// Example: cargo run --example motor_tool -- --port /dev/ttyACM0 scan
+//          cargo run --example motor_tool -- --port /dev/ttyACM0 calibrate -o calibration.json
+//          cargo run --example motor_tool -- --port /dev/ttyACM0 set-id 7
+
+use clap::{Parser, Subcommand};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+use lekiwi_zenoh_runtime::motor::eeprom;
+use lekiwi_zenoh_runtime::motor::feetech::{BAUD_RATES, DEFAULT_BAUDRATE, FeetechBus};
+use lekiwi_zenoh_runtime::motor::sim::SimServoBank;
+use lekiwi_zenoh_runtime::motor::{ARM_MOTOR_IDS, BASE_MOTOR_IDS, Calibrator};
+
+#[derive(Parser)]
+#[command(name = "motor_tool")]
+struct Args {
+    /// Serial port of the motor bus
+    #[arg(long, default_value = "/dev/tty.usbmodem58760432781")]
+    port: String,
+
+    /// Use a simulated LeKiwi servo chain instead of the serial port
+    #[arg(long, conflicts_with = "port")]
+    sim: bool,
+
+    /// Baud rate to open the bus at
+    #[arg(long, default_value_t = DEFAULT_BAUDRATE)]
+    baud: u32,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Find every servo on the bus, with model and firmware
+    Scan {
+        /// Baud rates to sweep (default: all supported)
+        #[arg(long, value_delimiter = ',')]
+        bauds: Vec<u32>,
+    },
+
+    /// Record a calibration by moving the motors by hand
+    Calibrate {
+        /// Motor IDs to calibrate (default: arm 1-6 and wheels 7-9)
+        #[arg(long, value_delimiter = ',')]
+        ids: Vec<u8>,
+
+        /// Continuously rotating motors, which get no range limits
+        #[arg(long, value_delimiter = ',', default_value = "7,8,9")]
+        continuous: Vec<u8>,
+
+        /// Calibration file to write
+        #[arg(short, long, default_value = "calibration.json")]
+        output: PathBuf,
+    },
+
+    /// Change the ID of a single servo (EEPROM write)
+    SetId {
+        /// New motor ID (0-253)
+        id: u8,
+    },
+
+    /// Change the baud rate of a single servo (EEPROM write)
+    SetBaud {
+        /// New baud rate
+        baud: u32,
+    },
+}
+
+fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N]: ", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().eq_ignore_ascii_case("y")
+}
+
+fn wait_for_enter(prompt: &str) {
+    print!("{} Press Enter when done.", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("warn".parse().unwrap()),
+        )
+        .init();
+
+    let args = Args::parse();
+    let mut bus = if args.sim {
+        let mut bus = FeetechBus::new(Box::new(SimServoBank::lekiwi().transport()));
+        bus.set_baud_rate(args.baud)?;
+        println!("Simulated bus at {} baud", args.baud);
+        bus
+    } else {
+        let bus = FeetechBus::open_with_baudrate(&args.port, args.baud)?;
+        println!("Serial port: {} at {} baud", args.port, args.baud);
+        bus
+    };
+    println!();
+
+    match args.command {
+        Command::Scan { bauds } => {
+            let bauds = if bauds.is_empty() {
+                BAUD_RATES.to_vec()
+            } else {
+                bauds
+            };
+            scan(&mut bus, &bauds)
+        }
+        Command::Calibrate {
+            ids,
+            continuous,
+            output,
+        } => {
+            let ids = if ids.is_empty() {
+                ARM_MOTOR_IDS.into_iter().chain(BASE_MOTOR_IDS).collect()
+            } else {
+                ids
+            };
+            calibrate(&mut bus, &ids, &continuous, &output)
+        }
+        Command::SetId { id } => {
+            confirm_single_servo()?;
+            let old_id = eeprom::change_id(&mut bus, id)?;
+            println!("✓ Servo ID {} -> {} (verified)", old_id, id);
+            Ok(())
+        }
+        Command::SetBaud { baud } => {
+            if !BAUD_RATES.contains(&baud) {
+                return Err(format!(
+                    "Unsupported baud rate {}: use one of {:?}",
+                    baud, BAUD_RATES
+                )
+                .into());
+            }
+            confirm_single_servo()?;
+            let id = eeprom::change_baud_rate(&mut bus, baud)?;
+            println!("✓ Servo {} now at {} baud (verified)", id, baud);
+            println!("  Open the bus with --baud {} to reach it", baud);
+            Ok(())
+        }
+    }
+}
+
+/// Make sure the user connected only the servo to change
+fn confirm_single_servo() -> Result<(), Box<dyn std::error::Error>> {
+    println!("  ⚠  This writes the servo's EEPROM.");
+    println!("  ⚠  Connect ONLY the servo to change; the tool refuses if more answer.");
+    if !confirm("Is exactly one servo connected?") {
+        return Err("Aborted".into());
+    }
+    println!("Scanning IDs 0-253...");
+    Ok(())
+}
+
+fn scan(bus: &mut FeetechBus, bauds: &[u32]) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Scanning IDs 0-253 at {:?} baud...", bauds);
+    let inventory = bus.scan_baud_rates(bauds)?;
+    if inventory.servos.is_empty() {
+        println!("  ✗ No servos found - check power and wiring");
+        return Ok(());
+    }
+
+    println!("  ID   Baud      Model   Firmware");
+    for servo in &inventory.servos {
+        let model = servo
+            .model_number
+            .map_or("?".to_string(), |m| m.to_string());
+        let firmware = servo.firmware.as_deref().unwrap_or("?");
+        println!(
+            "  {:<4} {:<9} {:<7} {}",
+            servo.id, servo.baud_rate, model, firmware
+        );
+    }
+    println!();
+    println!("✓ Found {} servo(s)", inventory.servos.len());
+
+    let mut ids: Vec<u8> = inventory.servos.iter().map(|s| s.id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() < inventory.servos.len() {
+        println!("  ⚠ Some IDs answer at several baud rates");
+    }
+    if inventory.servos.iter().any(|s| s.model_number.is_none()) {
+        println!("  ⚠ Servos marked ? answer pings but not reads: duplicate IDs?");
+    }
+    Ok(())
+}
+
+fn calibrate(
+    bus: &mut FeetechBus,
+    ids: &[u8],
+    continuous: &[u8],
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Step 1: Pinging motors {:?}...", ids);
+    for &id in ids {
+        if !bus.ping(id)? {
+            println!("  ✗ Motor {} NOT responding - aborting", id);
+            return Ok(());
+        }
+    }
+    println!("  ✓ All motors responding");
+    println!();
+
+    println!("  ⚠  Torque will be DISABLED on these motors.");
+    println!("  ⚠  Support the arm so it does not fall!");
+    if !confirm("Proceed with calibration?") {
+        println!("Aborted.");
+        return Ok(());
+    }
+    for &id in ids {
+        bus.disable_torque(id)?;
+    }
+    println!("  ✓ Torque disabled");
+    println!();
+
+    println!("Step 2: Home pose");
+    wait_for_enter("  Move every joint to its home (zero) pose.");
+    let mut calibrator = Calibrator::start(bus, ids)?;
+    println!("  ✓ Home recorded");
+    println!();
+
+    println!("Step 3: Directions");
+    wait_for_enter("  Push every motor a little in its POSITIVE direction.");
+    let unmoved = calibrator.record_directions(bus)?;
+    if unmoved.is_empty() {
+        println!("  ✓ Directions recorded");
+    } else {
+        println!(
+            "  ⚠ Motors {:?} did not move, keeping normal direction",
+            unmoved
+        );
+    }
+    println!();
+
+    println!("Step 4: Ranges");
+    println!("  Move every joint slowly through its full range (wheels: skip).");
+    print!("  Press Enter when done.");
+    io::stdout().flush()?;
+    let done = Arc::new(AtomicBool::new(false));
+    let stdin_done = done.clone();
+    std::thread::spawn(move || {
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+        stdin_done.store(true, Ordering::Relaxed);
+    });
+    while !done.load(Ordering::Relaxed) {
+        calibrator.sample_range(bus)?;
+        sleep(Duration::from_millis(20));
+    }
+    println!();
+
+    let calibration = calibrator.finish(continuous);
+    println!("  ID   Offset   Min    Max    Direction");
+    for (id, motor) in calibration.motors() {
+        println!(
+            "  {:<4} {:<8} {:<6} {:<6} {:?}",
+            id, motor.homing_offset, motor.range_min, motor.range_max, motor.direction
+        );
+    }
+    calibration.save(output)?;
+    println!();
+    println!("✓ Calibration saved to {}", output.display());
+    println!(
+        "  Run the runtime with --calibration {} to apply it",
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,123 @@
+// Replay a recording made with `lekiwi-runtime --record` onto Zenoh
+//
+//   cargo run --example replay -- session.jsonl --speed 2 --kinds command,estop
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::{Instant, sleep_until};
+use tracing::info;
+
+use lekiwi_zenoh_runtime::codec::WireEncoding;
+use lekiwi_zenoh_runtime::messages::wall_clock_ms;
+use lekiwi_zenoh_runtime::recorder::{Entry, read_recording, replay_offset};
+
+#[derive(Parser)]
+#[command(name = "replay")]
+struct Args {
+    /// JSON Lines recording to replay
+    file: PathBuf,
+
+    /// Playback speed (2.0 = twice as fast, 0.5 = half speed)
+    #[arg(long, default_value = "1.0")]
+    speed: f64,
+
+    /// Only replay these kinds (command, estop, actuation, health, wheels, arm, arm_state)
+    #[arg(long, value_delimiter = ',')]
+    kinds: Vec<String>,
+
+    /// Publish under this prefix instead of the recorded topics (e.g. "replay")
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Publish CBOR instead of JSON
+    #[arg(long)]
+    cbor: bool,
+
+    /// Connect to remote runtime at this IP (enables network mode)
+    #[arg(long)]
+    connect: Option<String>,
+
+    /// TCP port to connect to (default: 7447)
+    #[arg(long, default_value = "7447")]
+    port: u16,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let args = Args::parse();
+    tracing_subscriber::fmt().with_env_filter("info").init();
+
+    if !(args.speed.is_finite() && args.speed > 0.0) {
+        return Err("--speed must be a positive number".into());
+    }
+    let records: Vec<_> = read_recording(&args.file)?
+        .into_iter()
+        .filter(|r| args.kinds.is_empty() || args.kinds.iter().any(|k| k == r.entry.kind()))
+        .collect();
+    let encoding = if args.cbor {
+        WireEncoding::Cbor
+    } else {
+        WireEncoding::Json
+    };
+
+    let config = if let Some(ip) = &args.connect {
+        let mut config = zenoh::Config::default();
+        let endpoint = format!("tcp/{}:{}", ip, args.port);
+        config
+            .insert_json5("connect/endpoints", &format!("[\"{}\"]", endpoint))
+            .unwrap();
+        info!("Network mode: connecting to {}", endpoint);
+        config
+    } else {
+        info!("Local mode: using multicast discovery");
+        zenoh::Config::default()
+    };
+    let session = zenoh::open(config).await?;
+
+    let duration = records.last().map_or(Duration::ZERO, |r| r.offset());
+    info!(
+        "Replaying {} records ({:.1} s recorded) at {}x",
+        records.len(),
+        duration.as_secs_f64(),
+        args.speed
+    );
+
+    // Skip the idle time before the first record
+    let skip = records.first().map_or(Duration::ZERO, |r| r.offset());
+    let start = Instant::now();
+    // The runtime remembers sequence numbers per sender: replay as new senders
+    let replay_id = format!("replay-{}", wall_clock_ms());
+    for record in &records {
+        let offset = replay_offset(record, args.speed).saturating_sub(skip.div_f64(args.speed));
+        sleep_until(start + offset).await;
+
+        let topic = match &args.prefix {
+            Some(prefix) => format!("{}/{}", prefix, record.topic),
+            None => record.topic.clone(),
+        };
+        let mut entry = record.entry.clone();
+        // Stamped commands would be dropped as expired: keep their age, not their time
+        if let Entry::Command(cmd) = &mut entry
+            && let Some(stamp) = cmd.stamp_ms.as_mut()
+        {
+            let age = record.wall_ms.saturating_sub(*stamp);
+            *stamp = wall_clock_ms().saturating_sub(age);
+        }
+        if let Entry::Command(cmd) = &mut entry
+            && cmd.seq.is_some()
+        {
+            cmd.source = Some(match &cmd.source {
+                Some(source) => format!("{}/{}", source, replay_id),
+                None => replay_id.clone(),
+            });
+        }
+        session
+            .put(topic, entry.encode(encoding)?)
+            .encoding(encoding.zenoh_encoding())
+            .await?;
+    }
+
+    info!("Replay finished");
+    session.close().await?;
+    Ok(())
+}
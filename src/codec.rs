@@ -0,0 +1,183 @@
+// Wire encodings for runtime messages
+//
+// Every message in `messages.rs` can travel as JSON or CBOR. Publishers tag
+// their samples with the Zenoh encoding (`application/json` or
+// `application/cbor`) and receivers decode by that tag, so JSON and binary
+// clients can share the same topics. Samples without a recognised encoding
+// are treated as JSON, which keeps plain `z_put`-style clients working.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use zenoh::bytes::Encoding;
+
+/// Serialisation format of a message payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireEncoding {
+    Json,
+    /// Compact binary (RFC 8949)
+    Cbor,
+}
+
+/// Error encoding or decoding a message
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("CBOR encode error: {0}")]
+    CborEncode(#[from] ciborium::ser::Error<std::io::Error>),
+
+    #[error("CBOR decode error: {0}")]
+    CborDecode(#[from] ciborium::de::Error<std::io::Error>),
+}
+
+impl WireEncoding {
+    /// Zenoh encoding to tag samples with
+    pub fn zenoh_encoding(self) -> Encoding {
+        match self {
+            WireEncoding::Json => Encoding::APPLICATION_JSON,
+            WireEncoding::Cbor => Encoding::APPLICATION_CBOR,
+        }
+    }
+
+    /// Encoding of a received sample; anything but CBOR is read as JSON
+    pub fn from_zenoh(encoding: &Encoding) -> Self {
+        let mime = encoding.to_string();
+        match mime.split(';').next() {
+            Some("application/cbor") => WireEncoding::Cbor,
+            _ => WireEncoding::Json,
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, CodecError> {
+        match self {
+            WireEncoding::Json => Ok(serde_json::to_vec(value)?),
+            WireEncoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, payload: &[u8]) -> Result<T, CodecError> {
+        match self {
+            WireEncoding::Json => Ok(serde_json::from_slice(payload)?),
+            WireEncoding::Cbor => Ok(ciborium::from_reader(payload)?),
+        }
+    }
+}
+
+/// Decode a received sample according to its Zenoh encoding
+pub fn decode_sample<T: DeserializeOwned>(sample: &zenoh::sample::Sample) -> Result<T, CodecError> {
+    let payload = sample.payload().to_bytes();
+    WireEncoding::from_zenoh(sample.encoding()).decode(&payload)
+}
+
+/// Decode the payload of a query according to its encoding, `None` if empty
+pub fn decode_query<T: DeserializeOwned>(
+    query: &zenoh::query::Query,
+) -> Result<Option<T>, CodecError> {
+    let Some(payload) = query.payload().filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let encoding = query
+        .encoding()
+        .map_or(WireEncoding::Json, WireEncoding::from_zenoh);
+    encoding.decode(&payload.to_bytes()).map(Some)
+}
+
+/// Encoding to reply to a query with: `?encoding=cbor|json` if given, else
+/// the encoding of the query payload, else JSON
+pub fn reply_encoding(query: &zenoh::query::Query) -> WireEncoding {
+    match query.parameters().get("encoding") {
+        Some("cbor") => WireEncoding::Cbor,
+        Some("json") => WireEncoding::Json,
+        _ => query
+            .encoding()
+            .map_or(WireEncoding::Json, WireEncoding::from_zenoh),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{
+        BaseCommand, EstopCommand, HealthReason, RuntimeHealth, WheelFeedback, WheelState,
+    };
+    use crate::motor::feetech::MotorFault;
+
+    fn round_trip<T>(value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        for encoding in [WireEncoding::Json, WireEncoding::Cbor] {
+            let bytes = encoding.encode(value).unwrap();
+            assert_eq!(
+                &encoding.decode::<T>(&bytes).unwrap(),
+                value,
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        round_trip(&BaseCommand {
+            x_vel: 0.1,
+            y_vel: -0.2,
+            theta_vel: 30.0,
+            seq: Some(7),
+            source: Some("pad".to_string()),
+            ..Default::default()
+        });
+        round_trip(&EstopCommand::Engage {
+            disable_torque: true,
+            release_arm: true,
+        });
+        round_trip(&RuntimeHealth::from_reasons(vec![
+            HealthReason::CmdStale,
+            HealthReason::MotorError {
+                id: 8,
+                status: 0x24,
+                faults: vec![MotorFault::Overheat, MotorFault::Overload],
+            },
+        ]));
+        round_trip(&WheelState {
+            left: WheelFeedback {
+                id: 7,
+                measured_vel: -120,
+                voltage: 12.1,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_cbor_is_smaller_than_json() {
+        let state = WheelState::default();
+        let json = WireEncoding::Json.encode(&state).unwrap();
+        let cbor = WireEncoding::Cbor.encode(&state).unwrap();
+        assert!(cbor.len() < json.len());
+    }
+
+    #[test]
+    fn test_encoding_from_zenoh() {
+        assert_eq!(
+            WireEncoding::from_zenoh(&Encoding::APPLICATION_CBOR),
+            WireEncoding::Cbor
+        );
+        assert_eq!(
+            WireEncoding::from_zenoh(&Encoding::APPLICATION_JSON),
+            WireEncoding::Json
+        );
+        // Untagged samples (e.g. from older clients) are JSON
+        assert_eq!(
+            WireEncoding::from_zenoh(&Encoding::default()),
+            WireEncoding::Json
+        );
+    }
+}
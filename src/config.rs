@@ -1,6 +1,18 @@
 // Timeouts, topics, motor configuration
+//
+// The constants below are the built-in defaults. At startup they are merged
+// with an optional TOML/JSON5 config file and CLI/env overrides into a
+// `RuntimeConfig` (see `runtime::Args`).
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::codec::WireEncoding;
+use crate::limiter::{AccelLimits, AxisLimits};
+use crate::motor::kinematics::KinematicParams;
+use crate::motor::{ARM_MOTOR_IDS, BASE_MOTOR_IDS};
+use crate::mux::{CommandSource, DEFAULT_SOURCE, default_sources};
+
 // Runtime loop frequency
 pub const LOOP_HZ: u64 = 50;
 
@@ -9,8 +21,22 @@ pub const CMD_TIMEOUT: Duration = Duration::from_millis(250);
 
 // Zenoh topics
 pub const TOPIC_CMD_BASE: &str = "lekiwi/cmd/base"; // commands
+pub const TOPIC_ESTOP: &str = "lekiwi/cmd/estop"; // latching emergency stop
+pub const TOPIC_CMD_ARM: &str = "lekiwi/cmd/arm"; // arm joint positions
 pub const TOPIC_RT_BASE: &str = "lekiwi/rt/base"; // actuation
 pub const TOPIC_HEALTH: &str = "lekiwi/state/health"; // health status
+pub const TOPIC_ODOM: &str = "lekiwi/state/odom"; // wheel odometry
+pub const TOPIC_WHEELS: &str = "lekiwi/state/wheels"; // per-wheel telemetry
+pub const TOPIC_ARM_STATE: &str = "lekiwi/state/arm"; // arm joint state
+pub const TOPIC_INFO: &str = "lekiwi/runtime/info"; // status/config queryable
+pub const TOPIC_PARAMS: &str = "lekiwi/runtime/params"; // live parameter queryable
+pub const TOPIC_PARAM_CHANGES: &str = "lekiwi/runtime/params/changes"; // applied changes
+pub const TOPIC_SCAN: &str = "lekiwi/runtime/scan"; // motor bus scan queryable
+pub const TOPIC_SIM_POSE: &str = "lekiwi/sim/pose"; // ground truth in --sim mode
+
+// Payload encodings of published messages
+pub const TELEMETRY_ENCODING: WireEncoding = WireEncoding::Json; // actuation, odom, wheels
+pub const HEALTH_ENCODING: WireEncoding = WireEncoding::Json;
 
 // Motor configuration
 // Serial port for Feetech motor controller
@@ -18,3 +44,609 @@ pub const MOTOR_PORT: &str = "/dev/tty.usbmodem58760432781";
 
 // Enable hardware motor control (set to false for simulation/testing)
 pub const MOTOR_ENABLED: bool = true;
+
+// Drive the SO-100 arm on the same bus as the base; off so base-only robots connect
+pub const ARM_ENABLED: bool = false;
+
+// Delay between attempts to reopen a lost motor bus
+pub const MOTOR_RECONNECT_INTERVAL: Duration = Duration::from_millis(1000);
+
+// Simulated motors (--sim)
+pub const SIM_LAG: Duration = Duration::from_millis(80); // velocity time constant
+pub const SIM_MAX_VELOCITY: u16 = 2900; // raw steps/s, a bit below MAX_RAW
+
+// Speed limits applied to incoming commands
+pub const MAX_LINEAR_VEL: f32 = 0.5; // m/s
+pub const MAX_THETA_VEL: f32 = 180.0; // deg/s
+
+// Motor health thresholds
+pub const MAX_MOTOR_TEMPERATURE: u8 = 65; // °C
+pub const MIN_MOTOR_VOLTAGE: f32 = 9.5; // V
+
+/// Error loading or validating the runtime configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config file {path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+
+    #[error("Invalid value for `{key}`: {reason}")]
+    Invalid { key: String, reason: String },
+}
+
+impl ConfigError {
+    fn invalid(key: &str, reason: impl Into<String>) -> Self {
+        Self::Invalid {
+            key: key.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Complete runtime configuration
+///
+/// Every field has a default, so a config file only needs the keys it changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// Control loop frequency (Hz)
+    pub loop_hz: u64,
+    /// Watchdog timeout: stop if no command arrives within this window (ms)
+    pub cmd_timeout_ms: u64,
+    pub topics: TopicConfig,
+    pub encoding: EncodingConfig,
+    /// Prioritised command sources, each on `<topics.cmd_base>/<name>`
+    pub cmd_sources: Vec<CommandSource>,
+    pub motor: MotorConfig,
+    pub arm: ArmConfig,
+    pub kinematics: KinematicParams,
+    pub limits: SpeedLimits,
+    pub accel: AccelLimits,
+    pub health: HealthConfig,
+    pub sim: SimConfig,
+}
+
+/// Zenoh key expressions used by the runtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopicConfig {
+    pub cmd_base: String,
+    pub estop: String,
+    pub cmd_arm: String,
+    pub rt_base: String,
+    pub health: String,
+    pub odom: String,
+    pub wheels: String,
+    pub arm_state: String,
+    pub info: String,
+    pub params: String,
+    pub param_changes: String,
+    pub scan: String,
+    pub sim_pose: String,
+}
+
+/// Payload encodings of published messages
+///
+/// Received commands are decoded according to their sample encoding, so
+/// these only affect what the runtime publishes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncodingConfig {
+    /// Per-tick actuation, odometry, wheel and arm telemetry
+    pub telemetry: WireEncoding,
+    /// Health and parameter change events
+    pub health: WireEncoding,
+}
+
+/// Serial bus and motor IDs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MotorConfig {
+    pub enabled: bool,
+    pub port: String,
+    /// Base motor IDs as [left, back, right]
+    pub ids: [u8; 3],
+    /// Delay between attempts to reopen the bus after it was lost (ms)
+    pub reconnect_interval_ms: u64,
+    /// Calibration file (JSON keyed by motor ID); uncalibrated when unset
+    pub calibration: Option<PathBuf>,
+}
+
+/// SO-100 arm joints on the motor bus
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArmConfig {
+    pub enabled: bool,
+    /// Joint motor IDs as [shoulder_pan, shoulder_lift, elbow_flex, wrist_flex, wrist_roll, gripper]
+    pub ids: [u8; 6],
+}
+
+/// Kinematic simulation replacing the motor bus
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    /// Drive simulated wheels instead of `motor.port`
+    pub enabled: bool,
+    /// Time constant of the wheel velocity response (ms)
+    pub lag_ms: u64,
+    /// Wheel velocity saturation (raw steps/s)
+    pub max_velocity: u16,
+    /// Standard deviation of the measured wheel velocity noise (raw steps/s)
+    pub noise: f32,
+}
+
+/// Absolute limits applied to commanded body velocities
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedLimits {
+    pub max_x_vel: f32,     // m/s
+    pub max_y_vel: f32,     // m/s
+    pub max_theta_vel: f32, // deg/s
+}
+
+/// Thresholds for motor warnings in the published health
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    pub max_temperature: u8, // °C
+    pub min_voltage: f32,    // V
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            loop_hz: LOOP_HZ,
+            cmd_timeout_ms: CMD_TIMEOUT.as_millis() as u64,
+            topics: TopicConfig::default(),
+            encoding: EncodingConfig::default(),
+            cmd_sources: default_sources(),
+            motor: MotorConfig::default(),
+            arm: ArmConfig::default(),
+            kinematics: KinematicParams::default(),
+            limits: SpeedLimits::default(),
+            accel: AccelLimits::default(),
+            health: HealthConfig::default(),
+            sim: SimConfig::default(),
+        }
+    }
+}
+
+impl Default for TopicConfig {
+    fn default() -> Self {
+        Self {
+            cmd_base: TOPIC_CMD_BASE.to_string(),
+            estop: TOPIC_ESTOP.to_string(),
+            cmd_arm: TOPIC_CMD_ARM.to_string(),
+            rt_base: TOPIC_RT_BASE.to_string(),
+            health: TOPIC_HEALTH.to_string(),
+            odom: TOPIC_ODOM.to_string(),
+            wheels: TOPIC_WHEELS.to_string(),
+            arm_state: TOPIC_ARM_STATE.to_string(),
+            info: TOPIC_INFO.to_string(),
+            params: TOPIC_PARAMS.to_string(),
+            param_changes: TOPIC_PARAM_CHANGES.to_string(),
+            scan: TOPIC_SCAN.to_string(),
+            sim_pose: TOPIC_SIM_POSE.to_string(),
+        }
+    }
+}
+
+impl Default for EncodingConfig {
+    fn default() -> Self {
+        Self {
+            telemetry: TELEMETRY_ENCODING,
+            health: HEALTH_ENCODING,
+        }
+    }
+}
+
+impl Default for MotorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: MOTOR_ENABLED,
+            port: MOTOR_PORT.to_string(),
+            ids: BASE_MOTOR_IDS,
+            reconnect_interval_ms: MOTOR_RECONNECT_INTERVAL.as_millis() as u64,
+            calibration: None,
+        }
+    }
+}
+
+impl Default for ArmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: ARM_ENABLED,
+            ids: ARM_MOTOR_IDS,
+        }
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lag_ms: SIM_LAG.as_millis() as u64,
+            max_velocity: SIM_MAX_VELOCITY,
+            noise: 0.0,
+        }
+    }
+}
+
+impl Default for SpeedLimits {
+    fn default() -> Self {
+        Self {
+            max_x_vel: MAX_LINEAR_VEL,
+            max_y_vel: MAX_LINEAR_VEL,
+            max_theta_vel: MAX_THETA_VEL,
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_temperature: MAX_MOTOR_TEMPERATURE,
+            min_voltage: MIN_MOTOR_VOLTAGE,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Load a config file. `.json` / `.json5` files are parsed as JSON5,
+    /// everything else as TOML. The result is not validated yet.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let is_json = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("json" | "json5")
+        );
+        let parsed = if is_json {
+            json5::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+
+        parsed.map_err(|reason| ConfigError::Parse {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+
+    /// Watchdog timeout as a `Duration`
+    pub fn cmd_timeout(&self) -> Duration {
+        Duration::from_millis(self.cmd_timeout_ms)
+    }
+
+    /// All command sources: the legacy `topics.cmd_base` topic as the
+    /// lowest-priority `default` source, followed by `cmd_sources`
+    pub fn command_sources(&self) -> Vec<CommandSource> {
+        let default = CommandSource::new(DEFAULT_SOURCE, 0, self.cmd_timeout_ms);
+        std::iter::once(default)
+            .chain(self.cmd_sources.iter().cloned())
+            .collect()
+    }
+
+    /// Topic a command source listens on
+    pub fn source_topic(&self, source: &CommandSource) -> String {
+        if source.name == DEFAULT_SOURCE {
+            self.topics.cmd_base.clone()
+        } else {
+            format!("{}/{}", self.topics.cmd_base, source.name)
+        }
+    }
+
+    /// Motor bus reconnect interval as a `Duration`
+    pub fn reconnect_interval(&self) -> Duration {
+        Duration::from_millis(self.motor.reconnect_interval_ms)
+    }
+
+    /// Arm motor IDs on the bus, empty when the arm is disabled
+    pub fn arm_ids(&self) -> Vec<u8> {
+        if self.arm.enabled {
+            self.arm.ids.to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Control loop period as a `Duration`
+    pub fn loop_period(&self) -> Duration {
+        Duration::from_millis(1000 / self.loop_hz)
+    }
+
+    /// Check every value, reporting the first offending key
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(1..=1000).contains(&self.loop_hz) {
+            return Err(ConfigError::invalid(
+                "loop_hz",
+                "must be between 1 and 1000",
+            ));
+        }
+        if self.cmd_timeout_ms < 1000 / self.loop_hz {
+            return Err(ConfigError::invalid(
+                "cmd_timeout_ms",
+                format!(
+                    "must be at least one loop period ({} ms)",
+                    1000 / self.loop_hz
+                ),
+            ));
+        }
+
+        for (key, topic) in [
+            ("topics.cmd_base", &self.topics.cmd_base),
+            ("topics.estop", &self.topics.estop),
+            ("topics.cmd_arm", &self.topics.cmd_arm),
+            ("topics.rt_base", &self.topics.rt_base),
+            ("topics.health", &self.topics.health),
+            ("topics.odom", &self.topics.odom),
+            ("topics.wheels", &self.topics.wheels),
+            ("topics.arm_state", &self.topics.arm_state),
+            ("topics.info", &self.topics.info),
+            ("topics.params", &self.topics.params),
+            ("topics.param_changes", &self.topics.param_changes),
+            ("topics.scan", &self.topics.scan),
+            ("topics.sim_pose", &self.topics.sim_pose),
+        ] {
+            validate_topic(key, topic)?;
+        }
+
+        validate_sources(self)?;
+
+        if self.motor.enabled && self.motor.port.trim().is_empty() {
+            return Err(ConfigError::invalid(
+                "motor.port",
+                "must not be empty when motors are enabled",
+            ));
+        }
+        validate_motor_ids("motor.ids", &self.motor.ids)?;
+        // Checked together with the base IDs, which share the bus
+        let mut ids = self.motor.ids.to_vec();
+        ids.extend(self.arm_ids());
+        validate_motor_ids("arm.ids", &ids)?;
+        if self.motor.reconnect_interval_ms == 0 {
+            return Err(ConfigError::invalid(
+                "motor.reconnect_interval_ms",
+                "must be positive",
+            ));
+        }
+
+        let k = &self.kinematics;
+        validate_positive("kinematics.wheel_radius", k.wheel_radius)?;
+        validate_positive("kinematics.base_radius", k.base_radius)?;
+        if k.max_raw <= 0 {
+            return Err(ConfigError::invalid(
+                "kinematics.max_raw",
+                "must be positive",
+            ));
+        }
+
+        validate_positive("limits.max_x_vel", self.limits.max_x_vel)?;
+        validate_positive("limits.max_y_vel", self.limits.max_y_vel)?;
+        validate_positive("limits.max_theta_vel", self.limits.max_theta_vel)?;
+
+        validate_axis("accel.x", &self.accel.x)?;
+        validate_axis("accel.y", &self.accel.y)?;
+        validate_axis("accel.theta", &self.accel.theta)?;
+
+        if self.health.max_temperature == 0 {
+            return Err(ConfigError::invalid(
+                "health.max_temperature",
+                "must be positive",
+            ));
+        }
+        validate_positive("health.min_voltage", self.health.min_voltage)?;
+
+        if self.sim.max_velocity == 0 {
+            return Err(ConfigError::invalid("sim.max_velocity", "must be positive"));
+        }
+        if !(self.sim.noise.is_finite() && self.sim.noise >= 0.0) {
+            return Err(ConfigError::invalid(
+                "sim.noise",
+                format!("must be zero or positive, got {}", self.sim.noise),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_topic(key: &str, topic: &str) -> Result<(), ConfigError> {
+    zenoh::key_expr::KeyExpr::try_from(topic)
+        .map(|_| ())
+        .map_err(|e| ConfigError::invalid(key, e.to_string()))
+}
+
+fn validate_sources(config: &RuntimeConfig) -> Result<(), ConfigError> {
+    let period_ms = 1000 / config.loop_hz;
+    for (i, source) in config.cmd_sources.iter().enumerate() {
+        let key = format!("cmd_sources[{}]", i);
+        let name = &source.name;
+        if name.is_empty() || name.contains(['/', '*', '$', '?', '#']) {
+            return Err(ConfigError::invalid(
+                &format!("{}.name", key),
+                format!("`{}` is not a valid topic chunk", name),
+            ));
+        }
+        if name == DEFAULT_SOURCE || config.cmd_sources[..i].iter().any(|s| &s.name == name) {
+            return Err(ConfigError::invalid(
+                &format!("{}.name", key),
+                format!("`{}` is already used", name),
+            ));
+        }
+        if source.timeout_ms < period_ms {
+            return Err(ConfigError::invalid(
+                &format!("{}.timeout_ms", key),
+                format!("must be at least one loop period ({} ms)", period_ms),
+            ));
+        }
+        validate_topic(&format!("{}.name", key), &config.source_topic(source))?;
+    }
+    Ok(())
+}
+
+fn validate_motor_ids(key: &str, ids: &[u8]) -> Result<(), ConfigError> {
+    for (i, &id) in ids.iter().enumerate() {
+        // 0xFE is the broadcast ID, 0xFF is not addressable
+        if id >= 0xFE {
+            return Err(ConfigError::invalid(key, format!("ID {} is reserved", id)));
+        }
+        if ids[..i].contains(&id) {
+            return Err(ConfigError::invalid(
+                key,
+                format!("ID {} is used twice", id),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_axis(key: &str, axis: &AxisLimits) -> Result<(), ConfigError> {
+    validate_positive(&format!("{}.accel", key), axis.accel)?;
+    validate_positive(&format!("{}.decel", key), axis.decel)?;
+    validate_positive(&format!("{}.estop_decel", key), axis.estop_decel)?;
+    if let Some(jerk) = axis.jerk {
+        validate_positive(&format!("{}.jerk", key), jerk)?;
+    }
+    Ok(())
+}
+
+fn validate_positive(key: &str, value: f32) -> Result<(), ConfigError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(
+            key,
+            format!("must be a positive number, got {}", value),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_toml(text: &str) -> RuntimeConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        RuntimeConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config = parse_toml(include_str!("../lekiwi.example.toml"));
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_partial_toml_keeps_defaults() {
+        let config = parse_toml(
+            r#"
+            loop_hz = 100
+
+            [motor]
+            port = "/dev/ttyUSB0"
+
+            [encoding]
+            telemetry = "cbor"
+            "#,
+        );
+        assert_eq!(config.loop_hz, 100);
+        assert_eq!(config.motor.port, "/dev/ttyUSB0");
+        assert_eq!(config.motor.ids, BASE_MOTOR_IDS);
+        assert_eq!(config.topics, TopicConfig::default());
+        assert_eq!(config.encoding.telemetry, WireEncoding::Cbor);
+        assert_eq!(config.encoding.health, HEALTH_ENCODING);
+    }
+
+    #[test]
+    fn test_partial_axis_table_keeps_axis_defaults() {
+        let config = parse_toml(
+            r#"
+            [accel.x]
+            accel = 0.3
+
+            [accel.theta]
+            jerk = 3600.0
+            "#,
+        );
+        let defaults = AccelLimits::default();
+        assert_eq!(config.accel.x.accel, 0.3);
+        assert_eq!(config.accel.x.decel, defaults.x.decel);
+        assert_eq!(config.accel.y, defaults.y);
+        assert_eq!(config.accel.theta.accel, defaults.theta.accel);
+        assert_eq!(config.accel.theta.jerk, Some(3600.0));
+
+        let err = toml::from_str::<RuntimeConfig>("[accel.y]\naccl = 1.0\n").unwrap_err();
+        assert!(err.to_string().contains("accl"));
+    }
+
+    #[test]
+    fn test_json5_config() {
+        let config: RuntimeConfig =
+            json5::from_str("{ motor: { ids: [1, 2, 3] }, limits: { max_x_vel: 0.2, }, }").unwrap();
+        assert_eq!(config.motor.ids, [1, 2, 3]);
+        assert_eq!(config.limits.max_x_vel, 0.2);
+    }
+
+    #[test]
+    fn test_unknown_key_rejected() {
+        let err = toml::from_str::<RuntimeConfig>("[motor]\nprot = \"x\"\n").unwrap_err();
+        assert!(err.to_string().contains("prot"));
+    }
+
+    #[test]
+    fn test_validation_reports_key() {
+        let mut config = RuntimeConfig::default();
+        config.motor.ids = [7, 7, 9];
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "motor.ids"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Arm and base share the bus
+        let mut config = RuntimeConfig::default();
+        config.arm.enabled = true;
+        config.arm.ids[5] = 7;
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "arm.ids"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        config.arm.enabled = false;
+        config.validate().unwrap();
+
+        let mut config = RuntimeConfig::default();
+        config.kinematics.wheel_radius = 0.0;
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "kinematics.wheel_radius"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut config = RuntimeConfig::default();
+        config
+            .cmd_sources
+            .push(CommandSource::new("teleop", 5, 100));
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "cmd_sources[3].name"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut config = RuntimeConfig::default();
+        config.topics.health = "lekiwi//health".to_string();
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "topics.health"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
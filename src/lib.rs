@@ -1,5 +1,13 @@
 // For both public API and internal use - in main.rs for example
+pub mod codec;
 pub mod config;
+pub mod limiter;
 pub mod messages;
 pub mod motor;
+pub mod motor_thread;
+pub mod mux;
+pub mod odometry;
+pub mod params;
+pub mod recorder;
 pub mod runtime;
+pub mod simulation;
@@ -0,0 +1,287 @@
+// Acceleration and jerk limiting between commanded and actuated velocities
+//
+// Each body axis (x, y, theta) is rate limited independently: speeding up is
+// bounded by `accel`, slowing down by `decel`, and watchdog/emergency stops by
+// the (usually much larger) `estop_decel`. An optional jerk limit smooths the
+// start and end of every ramp.
+
+use serde::{Deserialize, Serialize};
+
+use crate::messages::BaseActuation;
+
+/// Remaining velocity error below which the output snaps to the target
+const SNAP_EPSILON: f32 = 1e-6;
+
+/// Limits for a single axis (units per second², jerk in units per second³)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AxisLimits {
+    /// Maximum acceleration when speeding up
+    pub accel: f32,
+    /// Maximum deceleration when slowing down or reversing
+    pub decel: f32,
+    /// Maximum deceleration for watchdog and emergency stops
+    pub estop_decel: f32,
+    /// Optional jerk limit (not applied to emergency stops)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jerk: Option<f32>,
+}
+
+/// Per-axis limits for the base (x/y in m/s², theta in deg/s²)
+///
+/// Keys missing from an axis table keep that axis' default.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "AccelLimitsPatch")]
+pub struct AccelLimits {
+    pub x: AxisLimits,
+    pub y: AxisLimits,
+    pub theta: AxisLimits,
+}
+
+impl Default for AccelLimits {
+    fn default() -> Self {
+        let linear = AxisLimits {
+            accel: 0.5,
+            decel: 1.0,
+            estop_decel: 2.5,
+            jerk: None,
+        };
+        Self {
+            x: linear,
+            y: linear,
+            theta: AxisLimits {
+                accel: 360.0,
+                decel: 720.0,
+                estop_decel: 1440.0,
+                jerk: None,
+            },
+        }
+    }
+}
+
+/// `AccelLimits` as written in a config file, where every key is optional
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AccelLimitsPatch {
+    x: AxisLimitsPatch,
+    y: AxisLimitsPatch,
+    theta: AxisLimitsPatch,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AxisLimitsPatch {
+    accel: Option<f32>,
+    decel: Option<f32>,
+    estop_decel: Option<f32>,
+    jerk: Option<f32>,
+}
+
+impl AxisLimitsPatch {
+    fn apply(self, defaults: AxisLimits) -> AxisLimits {
+        AxisLimits {
+            accel: self.accel.unwrap_or(defaults.accel),
+            decel: self.decel.unwrap_or(defaults.decel),
+            estop_decel: self.estop_decel.unwrap_or(defaults.estop_decel),
+            jerk: self.jerk.or(defaults.jerk),
+        }
+    }
+}
+
+impl From<AccelLimitsPatch> for AccelLimits {
+    fn from(patch: AccelLimitsPatch) -> Self {
+        let defaults = AccelLimits::default();
+        Self {
+            x: patch.x.apply(defaults.x),
+            y: patch.y.apply(defaults.y),
+            theta: patch.theta.apply(defaults.theta),
+        }
+    }
+}
+
+/// State of one rate-limited axis
+#[derive(Debug, Clone, Copy, Default)]
+struct AxisLimiter {
+    vel: f32,
+    acc: f32,
+}
+
+impl AxisLimiter {
+    /// Move `vel` towards `target` over `dt` seconds without exceeding the limits
+    fn step(&mut self, target: f32, limits: &AxisLimits, emergency: bool, dt: f32) -> f32 {
+        let dv = target - self.vel;
+        if dv == 0.0 || dt <= 0.0 {
+            self.acc = 0.0;
+            return self.vel;
+        }
+
+        // Speeding up = moving further away from zero in the same direction
+        let speeding_up = target.abs() > self.vel.abs() && target * self.vel >= 0.0;
+        let max_rate = if emergency {
+            limits.estop_decel
+        } else if speeding_up {
+            limits.accel
+        } else {
+            limits.decel
+        };
+
+        let acc = match limits.jerk {
+            Some(jerk) if jerk > 0.0 && !emergency => {
+                // Cap the acceleration so it can ramp back to zero by the time
+                // the target is reached, then move towards it at most jerk * dt
+                let reachable = (2.0 * jerk * dv.abs()).sqrt();
+                let desired = dv.signum() * max_rate.min(reachable);
+                self.acc + (desired - self.acc).clamp(-jerk * dt, jerk * dt)
+            }
+            _ => dv.signum() * max_rate,
+        };
+
+        let step = acc * dt;
+        if step.signum() == dv.signum() && step.abs() >= dv.abs() - SNAP_EPSILON {
+            // Would reach or overshoot: land exactly on the target
+            self.vel = target;
+            self.acc = 0.0;
+        } else {
+            self.vel += step;
+            self.acc = acc;
+        }
+        self.vel
+    }
+}
+
+/// Rate limiter for all three body axes
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    x: AxisLimiter,
+    y: AxisLimiter,
+    theta: AxisLimiter,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the limited output towards `target` by `dt` seconds
+    ///
+    /// `emergency` selects the emergency deceleration limits (e.g. watchdog trip).
+    pub fn apply(
+        &mut self,
+        target: &BaseActuation,
+        limits: &AccelLimits,
+        emergency: bool,
+        dt: f32,
+    ) -> BaseActuation {
+        BaseActuation {
+            x_vel: self.x.step(target.x_vel, &limits.x, emergency, dt),
+            y_vel: self.y.step(target.y_vel, &limits.y, emergency, dt),
+            theta_vel: self
+                .theta
+                .step(target.theta_vel, &limits.theta, emergency, dt),
+        }
+    }
+
+    /// Drop all motion state, e.g. after the motors were stopped externally
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.02;
+
+    fn limits(jerk: Option<f32>) -> AxisLimits {
+        AxisLimits {
+            accel: 0.5,
+            decel: 1.0,
+            estop_decel: 5.0,
+            jerk,
+        }
+    }
+
+    /// Run an axis towards `target` and return the number of steps taken
+    fn run_until(axis: &mut AxisLimiter, target: f32, l: &AxisLimits, emergency: bool) -> usize {
+        let mut steps = 0;
+        while axis.vel != target {
+            let before = axis.vel;
+            let after = axis.step(target, l, emergency, DT);
+            let rate = (after - before).abs() / DT;
+            assert!(rate <= 5.0 + 1e-3, "rate {} exceeds every limit", rate);
+            steps += 1;
+            assert!(steps < 10_000, "did not converge");
+        }
+        steps
+    }
+
+    #[test]
+    fn test_acceleration_is_limited() {
+        let mut axis = AxisLimiter::default();
+        let l = limits(None);
+        assert!((axis.step(0.3, &l, false, DT) - 0.01).abs() < 1e-6);
+        // 0.3 m/s at 0.5 m/s² takes 0.6 s = 30 ticks
+        assert_eq!(run_until(&mut axis, 0.3, &l, false), 29);
+    }
+
+    #[test]
+    fn test_deceleration_faster_than_acceleration() {
+        let l = limits(None);
+        let mut axis = AxisLimiter { vel: 0.3, acc: 0.0 };
+        assert_eq!(run_until(&mut axis, 0.0, &l, false), 15);
+
+        let mut axis = AxisLimiter { vel: 0.3, acc: 0.0 };
+        assert_eq!(run_until(&mut axis, 0.0, &l, true), 3);
+    }
+
+    #[test]
+    fn test_reversal_decelerates_then_accelerates() {
+        let l = limits(None);
+        let mut axis = AxisLimiter { vel: 0.2, acc: 0.0 };
+        axis.step(-0.2, &l, false, DT);
+        assert!(
+            (axis.vel - 0.18).abs() < 1e-6,
+            "decel while still moving forward"
+        );
+        run_until(&mut axis, -0.2, &l, false);
+        assert_eq!(axis.vel, -0.2);
+    }
+
+    #[test]
+    fn test_jerk_limits_acceleration_changes() {
+        let l = limits(Some(2.0));
+        let mut axis = AxisLimiter::default();
+        let mut last_acc = 0.0;
+        let mut peak_acc: f32 = 0.0;
+        while axis.step(0.3, &l, false, DT) != 0.3 {
+            // Every step before landing on the target respects the jerk limit
+            assert!((axis.acc - last_acc).abs() <= 2.0 * DT + 1e-5);
+            assert!(axis.acc <= l.accel + 1e-6);
+            last_acc = axis.acc;
+            peak_acc = peak_acc.max(axis.acc);
+        }
+        assert!(
+            peak_acc > 0.3,
+            "ramp should approach the acceleration limit"
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_axes_independent() {
+        let mut limiter = RateLimiter::new();
+        let target = BaseActuation {
+            x_vel: 0.3,
+            y_vel: 0.0,
+            theta_vel: 90.0,
+        };
+        let out = limiter.apply(&target, &AccelLimits::default(), false, DT);
+        assert!((out.x_vel - 0.01).abs() < 1e-6);
+        assert_eq!(out.y_vel, 0.0);
+        assert!((out.theta_vel - 7.2).abs() < 1e-4);
+
+        limiter.reset();
+        let out = limiter.apply(&BaseActuation::default(), &AccelLimits::default(), true, DT);
+        assert_eq!(out.x_vel, 0.0);
+    }
+}
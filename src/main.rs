@@ -11,4 +11,4 @@ async fn main() {
         eprintln!("Runtime error: {}", e);
         std::process::exit(1);
     }
-}
\ No newline at end of file
+}
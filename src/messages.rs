@@ -1,19 +1,43 @@
 // Define message types for the runtime
 
 use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::motor::feetech::{FeetechError, MotorFault};
+
+/// Wall-clock time in ms since the Unix epoch, as used by `stamp_ms`
+pub fn wall_clock_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
 
 // Command from teleop/scripts -> runtime
 // derive macro auto-implements print/debug, cloning, and (de)serialization for the following struct/enum
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Only the velocities are required; the optional metadata lets the runtime
+// drop reordered, duplicated or delayed packets
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct BaseCommand {
     pub x_vel: f32,
     pub y_vel: f32,
     pub theta_vel: f32,
+    /// Sender wall-clock time (ms since the Unix epoch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp_ms: Option<u64>,
+    /// Sequence number, increasing per sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// Sender id; sequence numbers are tracked per sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Time-to-live (ms), counted from `stamp_ms` if set, else from receipt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u64>,
 }
 
 // Actuation output from runtime -> lekiwi-hw
 // Has default values because we don't always have an actuation to send
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct BaseActuation {
     pub x_vel: f32,
     pub y_vel: f32,
@@ -32,10 +56,316 @@ impl From<&BaseCommand> for BaseActuation {
     }
 }
 
-/// Health status published by runtime
+// Emergency stop channel -> runtime
+// `engage` latches the stop until an explicit `reset`
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EstopCommand {
+    Engage {
+        /// Also disable motor torque (wheels spin freely)
+        #[serde(default)]
+        disable_torque: bool,
+        /// With `disable_torque`, release the arm joints too (the arm drops)
+        #[serde(default)]
+        release_arm: bool,
+    },
+    Reset,
+}
+
+/// Overall health level, ordered by severity
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
-pub enum RuntimeHealth {
+pub enum HealthLevel {
+    #[default]
     Ok,
+    Warn,
+    Error,
+}
+
+/// One reason the runtime is not fully healthy
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HealthReason {
+    /// Emergency stop latched: commands are ignored until reset
+    EstopLatched { torque_disabled: bool },
+    /// No fresh command: the watchdog holds the base at zero velocity
     CmdStale,
+    /// The serial link to the motor bus is down or could not be opened
+    BusDisconnected { error: String },
+    /// Motors are offline; the runtime keeps retrying to bring them back
+    MotorReconnecting { attempts: u32, retry_in_ms: u64 },
+    /// The motor thread has not reported for several ticks (e.g. stuck in bus timeouts)
+    MotorIoStalled { stalled_ms: u64 },
+    /// A motor did not answer (timeout)
+    MotorNotResponding { id: u8 },
+    /// Garbled or unexpected traffic on the bus
+    BusError { error: String },
+    /// A motor reported hardware faults in its status byte
+    MotorError {
+        id: u8,
+        status: u8,
+        faults: Vec<MotorFault>,
+    },
+    /// Motor temperature above `health.max_temperature`
+    OverTemperature {
+        id: u8,
+        temperature: u8, // °C
+        limit: u8,       // °C
+    },
+    /// Motor supply voltage below `health.min_voltage`
+    LowVoltage {
+        id: u8,
+        voltage: f32, // V
+        limit: f32,   // V
+    },
+}
+
+impl HealthReason {
+    pub fn level(&self) -> HealthLevel {
+        match self {
+            HealthReason::CmdStale
+            | HealthReason::BusError { .. }
+            | HealthReason::LowVoltage { .. } => HealthLevel::Warn,
+            HealthReason::EstopLatched { .. }
+            | HealthReason::BusDisconnected { .. }
+            | HealthReason::MotorReconnecting { .. }
+            | HealthReason::MotorIoStalled { .. }
+            | HealthReason::MotorNotResponding { .. }
+            | HealthReason::MotorError { .. }
+            | HealthReason::OverTemperature { .. } => HealthLevel::Error,
+        }
+    }
+}
+
+// Classifies a failed motor bus operation
+impl From<&FeetechError> for HealthReason {
+    fn from(error: &FeetechError) -> Self {
+        match *error {
+            FeetechError::Serial(_) | FeetechError::Io(_) => HealthReason::BusDisconnected {
+                error: error.to_string(),
+            },
+            FeetechError::Timeout { id } => HealthReason::MotorNotResponding { id },
+            FeetechError::MotorError { id, status } => HealthReason::MotorError {
+                id,
+                status,
+                faults: MotorFault::from_status(status),
+            },
+            _ => HealthReason::BusError {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+// Health status published by runtime -> monitoring
+// `level` is the most severe level among `reasons` (ok when empty)
+// Replaces the former bare "ok" / "cmd_stale" string payload (see README)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RuntimeHealth {
+    pub level: HealthLevel,
+    pub reasons: Vec<HealthReason>,
+    /// Command source currently driving the base, if any
+    #[serde(default)]
+    pub active_source: Option<String>,
+}
+
+impl RuntimeHealth {
+    pub fn from_reasons(reasons: Vec<HealthReason>) -> Self {
+        let level = reasons
+            .iter()
+            .map(HealthReason::level)
+            .max()
+            .unwrap_or_default();
+        Self {
+            level,
+            reasons,
+            active_source: None,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.level == HealthLevel::Ok
+    }
+}
+
+// Wheel odometry from runtime -> navigation
+// Pose is integrated in the odometry frame (origin = pose at runtime start),
+// velocities are in the body frame
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct BaseOdometry {
+    pub x: f32,         // m
+    pub y: f32,         // m
+    pub theta: f32,     // deg, wrapped to (-180, 180]
+    pub x_vel: f32,     // m/s
+    pub y_vel: f32,     // m/s
+    pub theta_vel: f32, // deg/s
+}
+
+// Feedback of a single wheel motor
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct WheelFeedback {
+    pub id: u8,
+    pub commanded_vel: i16, // raw steps/s sent as goal velocity
+    pub measured_vel: i16,  // raw steps/s read back
+    pub position: u16,      // raw steps (0-4095)
+    pub load: f32,          // % of max torque, signed
+    pub voltage: f32,       // V
+    pub temperature: u8,    // °C
+}
+
+// Per-wheel telemetry from runtime -> monitoring
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct WheelState {
+    pub left: WheelFeedback,
+    pub back: WheelFeedback,
+    pub right: WheelFeedback,
+}
+
+// One value per SO-100 arm joint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ArmJoints {
+    pub shoulder_pan: f32,
+    pub shoulder_lift: f32,
+    pub elbow_flex: f32,
+    pub wrist_flex: f32,
+    pub wrist_roll: f32,
+    pub gripper: f32,
+}
+
+impl ArmJoints {
+    /// Values in joint order (see `motor::JOINT_NAMES`)
+    pub fn to_array(self) -> [f32; 6] {
+        [
+            self.shoulder_pan,
+            self.shoulder_lift,
+            self.elbow_flex,
+            self.wrist_flex,
+            self.wrist_roll,
+            self.gripper,
+        ]
+    }
+
+    pub fn from_array(values: [f32; 6]) -> Self {
+        let [
+            shoulder_pan,
+            shoulder_lift,
+            elbow_flex,
+            wrist_flex,
+            wrist_roll,
+            gripper,
+        ] = values;
+        Self {
+            shoulder_pan,
+            shoulder_lift,
+            elbow_flex,
+            wrist_flex,
+            wrist_roll,
+            gripper,
+        }
+    }
+}
+
+// Arm command from teleop/scripts -> runtime
+// Every joint is required, so a partial message cannot move a joint to 0°
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ArmCommand {
+    pub positions: ArmJoints, // deg from the calibrated home
+}
+
+// Arm joint state from runtime -> monitoring
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ArmState {
+    pub position: ArmJoints, // deg from the calibrated home
+    pub velocity: ArmJoints, // deg/s
+    pub load: ArmJoints,     // % of max torque, signed
+}
+
+// Runtime introspection, replied on the info queryable -> tooling/teleop
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuntimeInfo {
+    pub build: BuildInfo,
+    /// Effective config after merging file, env and CLI overrides (same keys
+    /// as the config file)
+    pub config: serde_json::Value,
+    pub uptime_ms: u64,
+    pub motors: MotorState,
+    pub last_error: Option<LastError>,
+}
+
+// Identifies the running binary
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BuildInfo {
+    pub name: String,
+    pub version: String,
+    pub profile: String, // "debug" or "release"
+}
+
+impl BuildInfo {
+    /// Info of this binary, taken from the crate metadata at compile time
+    pub fn current() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+        }
+    }
+}
+
+// Motor initialization state
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum MotorState {
+    Disabled,      // motor.enabled = false
+    Uninitialized, // not initialized yet
+    Ready,
+    Offline { attempts: u32 }, // failed reconnect attempts so far
+}
+
+// Most recent motor error, kept after it clears
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LastError {
+    pub message: String,
+    pub at_ms: u64,  // wall-clock time (ms since the Unix epoch)
+    pub ago_ms: u64, // relative to the reply
+}
+
+// Request on the parameter queryable (an empty query lists all parameters)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ParamRequest {
+    List,
+    Get {
+        name: String,
+    },
+    Set {
+        name: String,
+        value: serde_json::Value,
+    },
+}
+
+// Request on the scan queryable (an empty query sweeps every supported baud rate)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanRequest {
+    pub baud_rates: Option<Vec<u32>>,
+}
+
+// A live parameter, keyed by its dotted config key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+// Parameter change applied by the runtime -> monitoring
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamChange {
+    pub name: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
 }
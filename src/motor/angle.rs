@@ -0,0 +1,152 @@
+// Servo angles in physical units
+//
+// STS3215 positions are raw encoder steps, 4096 per revolution, with 0° at
+// the middle of the range (step 2048). `Angle` converts between steps and
+// degrees or radians so position-mode callers never handle raw ticks.
+
+use super::feetech::PositionGoal;
+
+/// Encoder steps per revolution
+pub const STEPS_PER_REVOLUTION: i32 = 4096;
+
+/// Raw position of 0° (middle of the encoder range)
+pub const CENTER_STEPS: i32 = 2048;
+
+/// Encoder steps per degree
+pub const STEPS_PER_DEGREE: f32 = STEPS_PER_REVOLUTION as f32 / 360.0;
+
+/// Highest raw position
+pub const MAX_STEPS: i32 = STEPS_PER_REVOLUTION - 1;
+
+/// Highest goal speed (steps/s) below the sign bit
+pub const MAX_SPEED_STEPS: i32 = i16::MAX as i32;
+
+/// Servo angle from the center position
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Angle {
+    degrees: f32,
+}
+
+impl Angle {
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self { degrees }
+    }
+
+    pub fn from_radians(radians: f32) -> Self {
+        Self {
+            degrees: radians.to_degrees(),
+        }
+    }
+
+    /// Angle of a raw position (steps)
+    pub fn from_steps(steps: i32) -> Self {
+        Self::from_degrees((steps - CENTER_STEPS) as f32 / STEPS_PER_DEGREE)
+    }
+
+    pub fn degrees(self) -> f32 {
+        self.degrees
+    }
+
+    pub fn radians(self) -> f32 {
+        self.degrees.to_radians()
+    }
+
+    /// Raw position (steps), clamped to one turn
+    pub fn to_steps(self) -> i32 {
+        // Clamped before the cast, so huge angles cannot overflow; NaN casts to center
+        let from_center = (self.degrees * STEPS_PER_DEGREE)
+            .round()
+            .clamp(-CENTER_STEPS as f32, (MAX_STEPS - CENTER_STEPS) as f32);
+        from_center as i32 + CENTER_STEPS
+    }
+}
+
+/// Speed and acceleration of a position move, in physical units
+///
+/// `None` keeps whatever the servo is already set to.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MoveProfile {
+    pub speed: Option<f32>,        // deg/s
+    pub acceleration: Option<f32>, // deg/s²
+}
+
+impl MoveProfile {
+    /// Bus-level goal moving to a raw position with this profile
+    pub fn goal(self, position: i32) -> PositionGoal {
+        PositionGoal {
+            position,
+            // A speed of 0 means maximum and bit 15 is a sign, so keep slow moves at 1
+            speed: self
+                .speed
+                .map(|s| degrees_per_second_to_steps(s).clamp(1, MAX_SPEED_STEPS) as u16),
+            // Register unit is 100 steps/s²; never round a slow ramp down to 0 (= maximum)
+            acceleration: self.acceleration.map(|a| {
+                (a.abs() * STEPS_PER_DEGREE / 100.0)
+                    .ceil()
+                    .clamp(1.0, 254.0) as u8
+            }),
+        }
+    }
+}
+
+/// Convert a raw speed (steps/s) to deg/s
+pub fn steps_to_degrees_per_second(steps: i32) -> f32 {
+    steps as f32 / STEPS_PER_DEGREE
+}
+
+/// Convert a speed in deg/s to raw steps/s
+pub fn degrees_per_second_to_steps(degrees: f32) -> i32 {
+    (degrees.abs() * STEPS_PER_DEGREE).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Angle::from_degrees(0.0).to_steps(), 2048);
+        assert_eq!(Angle::from_degrees(90.0).to_steps(), 3072);
+        assert_eq!(Angle::from_degrees(-90.0).to_steps(), 1024);
+        assert_eq!(Angle::from_degrees(500.0).to_steps(), 4095);
+        assert_eq!(Angle::from_radians(-PI).to_steps(), 0);
+        assert_eq!(Angle::from_steps(3072).degrees(), 90.0);
+        assert!((Angle::from_steps(1024).radians() + PI / 2.0).abs() < 1e-6);
+        assert_eq!(degrees_per_second_to_steps(90.0), 1024);
+        assert_eq!(steps_to_degrees_per_second(-1024), -90.0);
+
+        let profile = MoveProfile {
+            speed: Some(180.0),
+            acceleration: Some(1.0),
+        };
+        assert_eq!(
+            profile.goal(Angle::from_degrees(45.0).to_steps()),
+            PositionGoal::new(2560)
+                .with_speed(2048)
+                .with_acceleration(1)
+        );
+        assert_eq!(MoveProfile::default().goal(2048), PositionGoal::new(2048));
+    }
+
+    #[test]
+    fn test_speed_is_clamped() {
+        let goal = |speed| MoveProfile {
+            speed: Some(speed),
+            ..Default::default()
+        };
+        assert_eq!(goal(0.0).goal(2048).speed, Some(1));
+        assert_eq!(goal(0.01).goal(2048).speed, Some(1));
+        assert_eq!(goal(-90.0).goal(2048).speed, Some(1024));
+        assert_eq!(goal(1e6).goal(2048).speed, Some(32767));
+    }
+
+    #[test]
+    fn test_huge_angles_clamp() {
+        assert_eq!(Angle::from_degrees(1e10).to_steps(), 4095);
+        assert_eq!(Angle::from_degrees(f32::INFINITY).to_steps(), 4095);
+        assert_eq!(Angle::from_degrees(-1e10).to_steps(), 0);
+        assert_eq!(Angle::from_degrees(f32::NEG_INFINITY).to_steps(), 0);
+        assert_eq!(Angle::from_degrees(f32::NAN).to_steps(), 2048);
+    }
+}
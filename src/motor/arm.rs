@@ -0,0 +1,163 @@
+// SO-100 arm on the LeKiwi motor bus
+//
+// The six arm joints share the Feetech chain with the wheels but run in
+// position mode. `ArmDriver` holds no bus of its own: `MotorDriver` owns the
+// bus and passes it in, so base and arm traffic never interleave mid-packet.
+
+use tracing::{debug, info, warn};
+
+use super::angle::{Angle, steps_to_degrees_per_second};
+use super::calibration::Calibration;
+use super::feetech::{FeetechBus, FeetechError, OperatingMode, PositionGoal, Register};
+
+/// Arm motor IDs, in joint order
+pub const ARM_MOTOR_IDS: [u8; 6] = [1, 2, 3, 4, 5, 6];
+
+/// Joint names, in the order of `ARM_MOTOR_IDS`
+pub const JOINT_NAMES: [&str; 6] = [
+    "shoulder_pan",
+    "shoulder_lift",
+    "elbow_flex",
+    "wrist_flex",
+    "wrist_roll",
+    "gripper",
+];
+
+/// Present state block read per joint: position, velocity, load
+const FEEDBACK_LEN: u8 = 6;
+
+/// Present state of one joint
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JointFeedback {
+    pub id: u8,
+    pub position: f32, // deg from center
+    pub velocity: f32, // deg/s
+    pub load: f32,     // % of max torque, signed
+}
+
+/// Position-mode driver for the six arm joints
+#[derive(Debug, Clone)]
+pub struct ArmDriver {
+    ids: [u8; 6],
+}
+
+impl ArmDriver {
+    pub fn new(ids: [u8; 6]) -> Self {
+        Self { ids }
+    }
+
+    pub fn ids(&self) -> [u8; 6] {
+        self.ids
+    }
+
+    /// Put the joints in position mode, holding where they are
+    ///
+    /// The goal is set to the present position before torque comes on, so
+    /// the arm does not jump to a stale goal.
+    pub fn initialize(&self, bus: &mut FeetechBus) -> Result<(), FeetechError> {
+        info!(
+            "Initializing arm motors {:?} for position control",
+            self.ids
+        );
+
+        for &id in &self.ids {
+            match bus.ping(id)? {
+                true => debug!("Arm motor {} responding", id),
+                false => {
+                    warn!("Arm motor {} not responding to ping", id);
+                    return Err(FeetechError::Timeout { id });
+                }
+            }
+        }
+
+        bus.set_torque(&self.ids, false)?;
+        bus.set_operating_modes(&self.ids, OperatingMode::Position)?;
+
+        let present = bus.sync_read_register(Register::PresentPosition, &self.ids)?;
+        let hold: Vec<(u8, i32)> = self.ids.iter().copied().zip(present).collect();
+        bus.sync_write_register(Register::GoalPosition, &hold)?;
+        bus.set_torque(&self.ids, true)
+    }
+
+    /// Move all joints to the given positions (deg from the calibrated home)
+    ///
+    /// Goals outside a joint's calibrated range are clamped to it. The joints
+    /// start moving together, and only once all of them acknowledged their goal.
+    pub fn set_positions(
+        &self,
+        bus: &mut FeetechBus,
+        calibration: &Calibration,
+        degrees: [f32; 6],
+    ) -> Result<(), FeetechError> {
+        let goals: Vec<(u8, PositionGoal)> = self
+            .ids
+            .iter()
+            .zip(degrees)
+            .map(|(&id, deg)| {
+                let steps = calibration.get(id).to_steps(Angle::from_degrees(deg));
+                (id, PositionGoal::new(steps))
+            })
+            .collect();
+        bus.staged_write_positions(&goals)
+    }
+
+    /// Read position, velocity and load of all joints, in calibrated units
+    pub fn read_feedback(
+        &self,
+        bus: &mut FeetechBus,
+        calibration: &Calibration,
+    ) -> Result<[JointFeedback; 6], FeetechError> {
+        let data = bus.sync_read(Register::PresentPosition, FEEDBACK_LEN, &self.ids)?;
+
+        let base = Register::PresentPosition.address();
+        let decode = |register: Register, bytes: &[u8]| {
+            register.decode(&bytes[(register.address() - base) as usize..])
+        };
+        let mut feedback = [JointFeedback::default(); 6];
+        for ((fb, &id), bytes) in feedback.iter_mut().zip(&self.ids).zip(&data) {
+            let cal = calibration.get(id);
+            let velocity = cal.apply_direction(decode(Register::PresentVelocity, bytes));
+            *fb = JointFeedback {
+                id,
+                position: cal
+                    .to_angle(decode(Register::PresentPosition, bytes))
+                    .degrees(),
+                velocity: steps_to_degrees_per_second(velocity),
+                load: cal.apply_direction(decode(Register::PresentLoad, bytes)) as f32 / 10.0,
+            };
+        }
+        Ok(feedback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::sim::SimServoBank;
+    use std::time::Duration;
+
+    #[test]
+    fn test_initialize_holds_and_moves() {
+        let bank = SimServoBank::new(&ARM_MOTOR_IDS);
+        bank.advance_clock(Duration::ZERO);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        let arm = ArmDriver::new(ARM_MOTOR_IDS);
+        arm.initialize(&mut bus).unwrap();
+        assert_eq!(bank.peek(3, Register::GoalPosition), Some(2048));
+
+        arm.set_positions(
+            &mut bus,
+            &Calibration::default(),
+            [0.0, 10.0, -20.0, 0.0, 0.0, 30.0],
+        )
+        .unwrap();
+        bank.advance_clock(Duration::from_millis(200));
+        let feedback = arm
+            .read_feedback(&mut bus, &Calibration::default())
+            .unwrap();
+        assert_eq!(feedback.map(|f| f.id), ARM_MOTOR_IDS);
+        for (fb, goal) in feedback.iter().zip([0.0, 10.0, -20.0, 0.0, 0.0, 30.0]) {
+            assert!((fb.position - goal).abs() < 0.1, "{:?}", fb);
+        }
+    }
+}
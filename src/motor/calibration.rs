@@ -0,0 +1,447 @@
+// Per-motor calibration
+//
+// A raw encoder position says nothing about where a joint actually is: every
+// servo is mounted with its own zero, some are mounted mirrored and most arm
+// joints can only travel part of a turn. A calibration records, per motor ID,
+// the homing offset (where the home pose sits relative to the servo center),
+// the reachable range and the drive direction. `MotorDriver` applies it when
+// converting angles and velocities to and from raw steps.
+//
+// The encoder wraps from 4095 to 0, so a joint whose home sits near either
+// end crosses the wrap within its range. Positions are unwrapped to within
+// half a turn of home before they are compared with the range or converted;
+// the range bounds are stored unwrapped and may lie outside 0..4095.
+//
+// Calibrations are recorded with `Calibrator` (see the `motor_tool calibrate`
+// example) and stored as JSON keyed by motor ID.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::angle::{Angle, CENTER_STEPS, MAX_STEPS, STEPS_PER_DEGREE, STEPS_PER_REVOLUTION};
+use super::feetech::{FeetechBus, FeetechError, Register};
+
+/// Smallest movement (steps) accepted as a deliberate push while detecting directions
+const DIRECTION_THRESHOLD: i32 = 20;
+
+/// Raw position within half a turn of `home`, counting past the encoder wrap
+fn unwrap_steps(steps: i32, home: i32) -> i32 {
+    home + (steps - home + CENTER_STEPS).rem_euclid(STEPS_PER_REVOLUTION) - CENTER_STEPS
+}
+
+/// Which way a motor turns for positive angles and velocities
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Normal,
+    /// Mounted mirrored: positive physical values are negative raw values
+    Inverted,
+}
+
+impl Direction {
+    pub fn sign(self) -> i32 {
+        match self {
+            Direction::Normal => 1,
+            Direction::Inverted => -1,
+        }
+    }
+}
+
+/// Calibration of one motor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotorCalibration {
+    /// Raw position of the home pose minus the servo center (steps)
+    pub homing_offset: i32,
+    /// Reachable raw positions (steps), unwrapped around home
+    pub range_min: i32,
+    pub range_max: i32,
+    pub direction: Direction,
+}
+
+impl Default for MotorCalibration {
+    /// Uncalibrated: home at the servo center, full turn, normal direction
+    fn default() -> Self {
+        Self {
+            homing_offset: 0,
+            range_min: 0,
+            range_max: MAX_STEPS,
+            direction: Direction::Normal,
+        }
+    }
+}
+
+impl MotorCalibration {
+    /// Raw position of the home pose
+    pub fn home(&self) -> i32 {
+        CENTER_STEPS + self.homing_offset
+    }
+
+    /// Angle from the home pose of a raw position
+    pub fn to_angle(&self, steps: i32) -> Angle {
+        let from_home = (unwrap_steps(steps, self.home()) - self.home()) * self.direction.sign();
+        Angle::from_degrees(from_home as f32 / STEPS_PER_DEGREE)
+    }
+
+    /// Raw position of an angle from the home pose, clamped to the range
+    pub fn to_steps(&self, angle: Angle) -> i32 {
+        // Beyond a turn the range clamps anyway; NaN casts to home
+        let degrees = angle.degrees().clamp(-360.0, 360.0);
+        let from_home = (degrees * STEPS_PER_DEGREE).round() as i32;
+        self.home()
+            .saturating_add(from_home * self.direction.sign())
+            .clamp(self.range_min, self.range_max)
+            .rem_euclid(STEPS_PER_REVOLUTION)
+    }
+
+    /// Raw velocity of a physical one (steps/s), and back
+    pub fn apply_direction(&self, velocity: i32) -> i32 {
+        velocity * self.direction.sign()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.homing_offset.unsigned_abs() > CENTER_STEPS as u32 {
+            return Err(format!(
+                "homing offset {} must be within ±{}",
+                self.homing_offset, CENTER_STEPS
+            ));
+        }
+        // In i64, so no range read from a file can overflow
+        let (min, max) = (self.range_min as i64, self.range_max as i64);
+        let (home, turn) = (self.home() as i64, STEPS_PER_REVOLUTION as i64);
+        if !(0..=MAX_STEPS as i64).contains(&(max - min)) || min < home - turn || max > home + turn
+        {
+            return Err(format!(
+                "range {}..{} must span at most {} steps within a turn of home {}",
+                self.range_min, self.range_max, MAX_STEPS, home
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Error loading or saving a calibration file
+#[derive(Debug, thiserror::Error)]
+pub enum CalibrationError {
+    #[error("Failed to access calibration file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid calibration file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("Invalid calibration for motor {id}: {reason}")]
+    Invalid { id: u8, reason: String },
+}
+
+/// Calibrations of all motors on a bus, keyed by motor ID
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Calibration {
+    motors: BTreeMap<u8, MotorCalibration>,
+}
+
+impl Calibration {
+    /// Read a calibration file
+    pub fn load(path: &Path) -> Result<Self, CalibrationError> {
+        let text = std::fs::read_to_string(path).map_err(|source| CalibrationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let calibration: Self =
+            serde_json::from_str(&text).map_err(|source| CalibrationError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        calibration.validate()?;
+        Ok(calibration)
+    }
+
+    /// Write a calibration file (pretty-printed JSON)
+    pub fn save(&self, path: &Path) -> Result<(), CalibrationError> {
+        let text = serde_json::to_string_pretty(self).expect("calibration is always serializable");
+        std::fs::write(path, text + "\n").map_err(|source| CalibrationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), CalibrationError> {
+        for (&id, motor) in &self.motors {
+            motor
+                .validate()
+                .map_err(|reason| CalibrationError::Invalid { id, reason })?;
+        }
+        Ok(())
+    }
+
+    /// Calibration of a motor; uncalibrated motors get the default
+    pub fn get(&self, id: u8) -> MotorCalibration {
+        self.motors.get(&id).copied().unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, id: u8, calibration: MotorCalibration) {
+        self.motors.insert(id, calibration);
+    }
+
+    /// Calibrated motors, by ID
+    pub fn motors(&self) -> impl Iterator<Item = (u8, &MotorCalibration)> {
+        self.motors.iter().map(|(&id, c)| (id, c))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.motors.is_empty()
+    }
+}
+
+/// Records a calibration from motors moved by hand
+///
+/// 1. `start` releases torque and takes the present pose as home
+/// 2. `record_directions` after pushing every motor a bit in its positive direction
+/// 3. `sample_range`, repeatedly, while every joint is moved through its range
+/// 4. `finish`, naming the motors that rotate continuously
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    ids: Vec<u8>,
+    home: Vec<i32>,
+    min: Vec<i32>,
+    max: Vec<i32>,
+    directions: Vec<Direction>,
+}
+
+impl Calibrator {
+    /// Release the motors and record their present positions as home
+    pub fn start(bus: &mut FeetechBus, ids: &[u8]) -> Result<Self, FeetechError> {
+        for &id in ids {
+            bus.disable_torque(id)?;
+        }
+        let home = bus.sync_read_register(Register::PresentPosition, ids)?;
+        Ok(Self {
+            ids: ids.to_vec(),
+            min: home.clone(),
+            max: home.clone(),
+            home,
+            directions: vec![Direction::Normal; ids.len()],
+        })
+    }
+
+    pub fn ids(&self) -> &[u8] {
+        &self.ids
+    }
+
+    /// Take the direction each motor was pushed away from home as positive
+    ///
+    /// Returns the IDs of motors that did not move far enough to tell; they
+    /// keep the normal direction.
+    pub fn record_directions(&mut self, bus: &mut FeetechBus) -> Result<Vec<u8>, FeetechError> {
+        let present = bus.sync_read_register(Register::PresentPosition, &self.ids)?;
+        let mut unmoved = Vec::new();
+        for (i, position) in present.into_iter().enumerate() {
+            let delta = unwrap_steps(position, self.home[i]) - self.home[i];
+            self.directions[i] = if delta >= DIRECTION_THRESHOLD {
+                Direction::Normal
+            } else if delta <= -DIRECTION_THRESHOLD {
+                Direction::Inverted
+            } else {
+                unmoved.push(self.ids[i]);
+                Direction::Normal
+            };
+        }
+        Ok(unmoved)
+    }
+
+    /// Widen the recorded ranges with the present positions
+    ///
+    /// Returns the ranges so far as (id, min, max), unwrapped around home.
+    pub fn sample_range(
+        &mut self,
+        bus: &mut FeetechBus,
+    ) -> Result<Vec<(u8, i32, i32)>, FeetechError> {
+        let present = bus.sync_read_register(Register::PresentPosition, &self.ids)?;
+        for (i, position) in present.into_iter().enumerate() {
+            let position = unwrap_steps(position, self.home[i]);
+            self.min[i] = self.min[i].min(position);
+            self.max[i] = self.max[i].max(position);
+        }
+        Ok((0..self.ids.len())
+            .map(|i| (self.ids[i], self.min[i], self.max[i]))
+            .collect())
+    }
+
+    /// The recorded calibration
+    ///
+    /// Continuously rotating motors (the wheels) and motors that were never
+    /// moved through a range get the full turn around home.
+    pub fn finish(self, continuous: &[u8]) -> Calibration {
+        let mut calibration = Calibration::default();
+        for (i, &id) in self.ids.iter().enumerate() {
+            let (range_min, range_max) = if self.min[i] < self.max[i] && !continuous.contains(&id) {
+                (self.min[i], self.max[i])
+            } else {
+                (
+                    self.home[i] - CENTER_STEPS,
+                    self.home[i] + MAX_STEPS - CENTER_STEPS,
+                )
+            };
+            calibration.insert(
+                id,
+                MotorCalibration {
+                    homing_offset: self.home[i] - CENTER_STEPS,
+                    range_min,
+                    range_max,
+                    direction: self.directions[i],
+                },
+            );
+        }
+        calibration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::sim::SimServoBank;
+
+    #[test]
+    fn test_conversions() {
+        let calibration = MotorCalibration {
+            homing_offset: 100,
+            range_min: 1000,
+            range_max: 3000,
+            direction: Direction::Inverted,
+        };
+        assert_eq!(calibration.to_angle(2148).degrees(), 0.0);
+        assert_eq!(calibration.to_angle(2148 - 1024).degrees(), 90.0);
+        assert_eq!(calibration.to_steps(Angle::from_degrees(90.0)), 1124);
+        // Clamped to the recorded range
+        assert_eq!(calibration.to_steps(Angle::from_degrees(-180.0)), 3000);
+        assert_eq!(calibration.apply_direction(300), -300);
+
+        let identity = MotorCalibration::default();
+        assert_eq!(identity.to_steps(Angle::from_degrees(90.0)), 3072);
+        assert_eq!(identity.to_angle(1024).degrees(), -90.0);
+    }
+
+    #[test]
+    fn test_out_of_range_angles_clamp() {
+        let calibration = MotorCalibration {
+            homing_offset: 100,
+            range_min: 1000,
+            range_max: 3000,
+            direction: Direction::Inverted,
+        };
+        for (degrees, steps) in [
+            (1e10, 1000),
+            (f32::INFINITY, 1000),
+            (-1e10, 3000),
+            (f32::NEG_INFINITY, 3000),
+            (f32::NAN, 2148),
+        ] {
+            assert_eq!(
+                calibration.to_steps(Angle::from_degrees(degrees)),
+                steps,
+                "{}",
+                degrees
+            );
+        }
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let bank = SimServoBank::new(&[1, 2, 7]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        bank.set_position(1, 2248);
+        let mut calibrator = Calibrator::start(&mut bus, &[1, 2, 7]).unwrap();
+
+        bank.set_position(1, 2148); // pushed down: inverted
+        bank.set_position(2, 2148); // pushed up: normal
+        assert_eq!(calibrator.record_directions(&mut bus).unwrap(), vec![7]);
+
+        for (one, two) in [(1500, 1800), (3000, 2600)] {
+            bank.set_position(1, one);
+            bank.set_position(2, two);
+            calibrator.sample_range(&mut bus).unwrap();
+        }
+        bank.set_position(7, 3000);
+        calibrator.sample_range(&mut bus).unwrap();
+        let calibration = calibrator.finish(&[7]);
+        assert_eq!(
+            calibration.get(1),
+            MotorCalibration {
+                homing_offset: 200,
+                range_min: 1500,
+                range_max: 3000,
+                direction: Direction::Inverted,
+            }
+        );
+        assert_eq!(calibration.get(2).direction, Direction::Normal);
+        assert_eq!(calibration.get(7), MotorCalibration::default());
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(0));
+
+        let path = std::env::temp_dir().join(format!("lekiwi-cal-{}.json", std::process::id()));
+        calibration.save(&path).unwrap();
+        let loaded = Calibration::load(&path).unwrap();
+        std::fs::write(&path, r#"{"7": {"homing_offset": 0, "range_min": 10, "range_max": 5, "direction": "normal"}}"#).unwrap();
+        let invalid = Calibration::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, calibration);
+        assert!(matches!(
+            invalid,
+            Err(CalibrationError::Invalid { id: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_rejects_overflowing_values() {
+        let path = std::env::temp_dir().join(format!("lekiwi-cal-max-{}.json", std::process::id()));
+        let mut results = Vec::new();
+        for motor in [
+            r#"{"homing_offset": -2147483648, "range_min": 0, "range_max": 4095, "direction": "normal"}"#,
+            r#"{"homing_offset": 0, "range_min": -2147483648, "range_max": 2147483647, "direction": "normal"}"#,
+            r#"{"homing_offset": 0, "range_min": 2147483647, "range_max": -2147483648, "direction": "normal"}"#,
+        ] {
+            std::fs::write(&path, format!(r#"{{"7": {}}}"#, motor)).unwrap();
+            results.push(Calibration::load(&path));
+        }
+        std::fs::remove_file(&path).unwrap();
+        for result in results {
+            assert!(
+                matches!(result, Err(CalibrationError::Invalid { id: 7, .. })),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_across_the_encoder_wrap() {
+        let bank = SimServoBank::new(&[1]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        bank.set_position(1, 100);
+        let mut calibrator = Calibrator::start(&mut bus, &[1]).unwrap();
+        bank.set_position(1, 300);
+        calibrator.record_directions(&mut bus).unwrap();
+        for position in [4000, 300] {
+            bank.set_position(1, position);
+            calibrator.sample_range(&mut bus).unwrap();
+        }
+        let calibration = calibrator.finish(&[]).get(1);
+        assert_eq!((calibration.range_min, calibration.range_max), (-96, 300));
+        calibration.validate().unwrap();
+
+        assert_eq!(
+            calibration.to_angle(4000).degrees(),
+            -196.0 / STEPS_PER_DEGREE
+        );
+        assert_eq!(calibration.to_steps(Angle::from_degrees(-10.0)), 4082);
+        // Clamped at the wrapped end of the range
+        assert_eq!(calibration.to_steps(Angle::from_degrees(-90.0)), 4000);
+    }
+}
@@ -0,0 +1,386 @@
+// Streaming decoder for status packets
+//
+// Status packets reach the host in arbitrary fragments, and not always
+// cleanly: line noise ahead of a reply, a reply mangled by a collision on the
+// bus, or a half-duplex adapter that hands every transmitted byte back before
+// the servos answer. `PacketDecoder` buffers whatever the port delivers and
+// hands out complete, checksummed status packets. A candidate packet with an
+// impossible length or a bad checksum is dropped one byte at a time, so the
+// decoder resynchronises on the next header instead of giving up.
+//
+// Whether the adapter echoes is learned from the first exchange that tells:
+// a copy of the request followed by more bytes, or a copy of a request that
+// could not be a reply (a broadcast, or an instruction byte with bits no status
+// byte has). A lone copy of a request that could be a reply, e.g. a ping
+// answered with the voltage fault bit, stays undecided until `set_echo`.
+
+use tracing::debug;
+
+use super::feetech::{BROADCAST_ID, FeetechBus, HEADER, MotorFault};
+
+/// Most parameter bytes in a status packet: a read never spans more than the register map
+pub const MAX_PARAMS: usize = 128;
+
+/// Bounds of the length byte: error and checksum, plus the parameters
+const MIN_LENGTH: u8 = 2;
+const MAX_LENGTH: u8 = MAX_PARAMS as u8 + 2;
+
+/// Header, ID and length
+const PREFIX_LEN: usize = 4;
+
+/// A status packet that passed its checksum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusPacket {
+    pub id: u8,
+    /// Status bits, 0 when the servo reports no fault
+    pub error: u8,
+    pub params: Vec<u8>,
+}
+
+/// Why a candidate packet was thrown away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// Length byte outside the bounds of a status packet
+    Length { id: u8, length: u8 },
+    /// Checksum does not match the packet
+    Checksum { id: u8 },
+}
+
+/// Whether the adapter echoes transmitted bytes, learned from the first reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Echo {
+    Unknown,
+    Echoes,
+    Silent,
+}
+
+/// Reassembles status packets from a byte stream
+#[derive(Debug)]
+pub struct PacketDecoder {
+    buffer: Vec<u8>,
+    echo: Echo,
+    sent: Vec<u8>, // transmitted packet an echoing adapter sends back first
+    rejected: Option<Rejection>,
+}
+
+impl Default for PacketDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacketDecoder {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            echo: Echo::Unknown,
+            sent: Vec::new(),
+            rejected: None,
+        }
+    }
+
+    /// Start a new exchange, right after `packet` was transmitted
+    ///
+    /// Bytes left over from the previous exchange are dropped. If the adapter
+    /// echoes, the copy of `packet` ahead of the replies is skipped.
+    pub fn start(&mut self, packet: &[u8]) {
+        self.buffer.clear();
+        self.sent.clear();
+        if self.echo != Echo::Silent {
+            self.sent.extend_from_slice(packet);
+        }
+        self.rejected = None;
+    }
+
+    /// Append bytes read from the port
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Next complete status packet, if the bytes received so far hold one
+    pub fn next_packet(&mut self) -> Option<StatusPacket> {
+        loop {
+            self.sync();
+            if !self.sent.is_empty() && self.skip_echo()? {
+                continue;
+            }
+            if self.buffer.len() < PREFIX_LEN {
+                return None;
+            }
+
+            let id = self.buffer[2];
+            let length = self.buffer[3];
+            if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+                self.reject(Rejection::Length { id, length });
+                continue;
+            }
+            let total = PREFIX_LEN + length as usize;
+            if self.buffer.len() < total {
+                return None;
+            }
+            if FeetechBus::checksum(&self.buffer[2..total - 1]) != self.buffer[total - 1] {
+                self.reject(Rejection::Checksum { id });
+                continue;
+            }
+
+            let packet = StatusPacket {
+                id,
+                error: self.buffer[PREFIX_LEN],
+                params: self.buffer[PREFIX_LEN + 1..total - 1].to_vec(),
+            };
+            self.buffer.drain(..total);
+            return Some(packet);
+        }
+    }
+
+    /// Most recent candidate packet thrown away, cleared by reading it
+    pub fn take_rejected(&mut self) -> Option<Rejection> {
+        self.rejected.take()
+    }
+
+    /// Drop everything before the first header
+    ///
+    /// Runs of 0xFF are line idle or garbage: the header is the last two of them.
+    fn sync(&mut self) {
+        let start = (0..self.buffer.len())
+            .find(|&i| {
+                self.buffer[i..].starts_with(&HEADER) && self.buffer.get(i + 2) != Some(&0xFF)
+            })
+            .unwrap_or(self.buffer.len() - usize::from(self.buffer.last() == Some(&0xFF)));
+        if start > 0 {
+            debug!(
+                "Discarding {} garbage bytes: {:02X?}",
+                start,
+                &self.buffer[..start]
+            );
+            self.buffer.drain(..start);
+        }
+    }
+
+    /// Skip the echo of the transmitted packet at the front of the buffer
+    ///
+    /// Returns `None` while the bytes so far could still be the echo,
+    /// `Some(true)` after skipping it and `Some(false)` if there is none.
+    fn skip_echo(&mut self) -> Option<bool> {
+        let n = self.buffer.len().min(self.sent.len());
+        if self.buffer[..n] != self.sent[..n] {
+            if self.echo == Echo::Unknown {
+                debug!("Bus adapter does not echo transmitted bytes");
+                self.echo = Echo::Silent;
+            }
+            self.sent.clear();
+            return Some(false);
+        }
+        if n < self.sent.len() {
+            return None;
+        }
+        if self.echo == Echo::Unknown {
+            if self.buffer.len() == n && Self::could_be_reply(&self.sent) {
+                return None; // the echo or an identical reply: wait for more
+            }
+            debug!("Bus adapter echoes transmitted bytes, skipping them");
+            self.echo = Echo::Echoes;
+        }
+        self.buffer.drain(..n);
+        self.sent.clear();
+        Some(true)
+    }
+
+    /// Whether a transmitted packet could also be read as a status packet
+    fn could_be_reply(packet: &[u8]) -> bool {
+        packet.get(2).is_some_and(|&id| id != BROADCAST_ID)
+            && packet.get(4).is_some_and(|&b| b & !MotorFault::MASK == 0)
+    }
+
+    /// Whether the bytes so far are a copy of the request that may be its echo or its reply
+    pub fn echo_undecided(&self) -> bool {
+        self.echo == Echo::Unknown
+            && !self.sent.is_empty()
+            && self.buffer.len() == self.sent.len()
+            && self.buffer == self.sent
+    }
+
+    /// Settle whether the adapter echoes, e.g. after probing it
+    ///
+    /// An undecided copy of the request is then skipped as the echo or
+    /// decoded as the reply.
+    pub fn set_echo(&mut self, echoes: bool) {
+        debug!("Bus adapter echoes transmitted bytes: {}", echoes);
+        self.echo = if echoes { Echo::Echoes } else { Echo::Silent };
+        if !echoes {
+            self.sent.clear();
+        }
+    }
+
+    /// Throw away a candidate packet: drop its first byte and resynchronise
+    fn reject(&mut self, rejection: Rejection) {
+        debug!("Dropping invalid packet: {:?}", rejection);
+        self.rejected = Some(rejection);
+        self.buffer.drain(..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(id: u8, error: u8, params: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF, id, params.len() as u8 + 2, error];
+        packet.extend_from_slice(params);
+        packet.push(FeetechBus::checksum(&packet[2..]));
+        packet
+    }
+
+    fn decode_all(decoder: &mut PacketDecoder) -> Vec<StatusPacket> {
+        std::iter::from_fn(|| decoder.next_packet()).collect()
+    }
+
+    #[test]
+    fn test_fragmented_stream() {
+        let mut stream = status(7, 0, &[0x10, 0x20]);
+        stream.extend(status(8, 0x20, &[]));
+
+        // Byte by byte, then in uneven chunks: always the same two packets
+        for chunk in [1, 3, 5, stream.len()] {
+            let mut decoder = PacketDecoder::new();
+            decoder.start(&[]);
+            let mut packets = Vec::new();
+            for bytes in stream.chunks(chunk) {
+                decoder.push(bytes);
+                packets.extend(decode_all(&mut decoder));
+            }
+            assert_eq!(
+                packets,
+                vec![
+                    StatusPacket {
+                        id: 7,
+                        error: 0,
+                        params: vec![0x10, 0x20],
+                    },
+                    StatusPacket {
+                        id: 8,
+                        error: 0x20,
+                        params: vec![],
+                    },
+                ],
+                "chunks of {}",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn test_garbage_is_skipped() {
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&[]);
+        decoder.push(&[0x00, 0x13, 0xFF, 0x42, 0xFF, 0xFF, 0xFF]);
+        decoder.push(&status(7, 0, &[1]));
+        decoder.push(&[0xFF]);
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![StatusPacket {
+                id: 7,
+                error: 0,
+                params: vec![1],
+            }]
+        );
+        assert_eq!(decoder.take_rejected(), None);
+
+        // A trailing 0xFF may start the next header
+        decoder.push(&status(8, 0, &[2])[1..]);
+        assert_eq!(decode_all(&mut decoder)[0].id, 8);
+    }
+
+    #[test]
+    fn test_corrupted_packets_resync() {
+        // Length 0 and 1 cannot hold error and checksum; length 200 exceeds any reply
+        for length in [0, 1, 200] {
+            let mut decoder = PacketDecoder::new();
+            decoder.start(&[]);
+            decoder.push(&[0xFF, 0xFF, 7, length]);
+            decoder.push(&status(7, 0, &[3]));
+            assert_eq!(
+                decode_all(&mut decoder)[..],
+                [StatusPacket {
+                    id: 7,
+                    error: 0,
+                    params: vec![3],
+                }]
+            );
+            assert_eq!(
+                decoder.take_rejected(),
+                Some(Rejection::Length { id: 7, length })
+            );
+        }
+
+        // A bad checksum drops the packet; a valid one hidden behind it still decodes
+        let mut corrupted = status(7, 0, &[0xFF, 0xFF]);
+        *corrupted.last_mut().unwrap() ^= 0x01;
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&[]);
+        decoder.push(&corrupted);
+        assert_eq!(decode_all(&mut decoder), vec![]);
+        assert_eq!(decoder.take_rejected(), Some(Rejection::Checksum { id: 7 }));
+        decoder.push(&status(8, 0, &[4]));
+        assert_eq!(decode_all(&mut decoder)[0].id, 8);
+    }
+
+    #[test]
+    fn test_echo_is_skipped() {
+        // Ping of ID 7 and its reply differ only in the instruction/error byte
+        let ping = vec![0xFF, 0xFF, 7, 2, 0x01, FeetechBus::checksum(&[7, 2, 0x01])];
+        let reply = status(7, 0, &[]);
+
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&ping);
+        decoder.push(&ping[..3]);
+        assert_eq!(decoder.next_packet(), None);
+        decoder.push(&ping[3..]);
+        decoder.push(&reply);
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![StatusPacket {
+                id: 7,
+                error: 0,
+                params: vec![],
+            }]
+        );
+
+        // Learned: the next echo is skipped as well
+        decoder.start(&ping);
+        decoder.push(&[ping.clone(), reply.clone()].concat());
+        assert_eq!(decode_all(&mut decoder).len(), 1);
+
+        // A copy of a ping alone may be the echo or a reply with the voltage
+        // fault bit; it stays undecided until the adapter is known
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&ping);
+        decoder.push(&ping);
+        assert_eq!(decoder.next_packet(), None);
+        assert!(decoder.echo_undecided());
+        decoder.set_echo(false);
+        assert_eq!(decode_all(&mut decoder)[0].error, 0x01);
+
+        // A broadcast cannot be a reply: its copy is an echo
+        let mut sync_read = vec![0xFF, 0xFF, BROADCAST_ID, 5, 0x82, 56, 2, 7];
+        sync_read.push(FeetechBus::checksum(&sync_read[2..]));
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&sync_read);
+        decoder.push(&sync_read);
+        assert_eq!(decoder.next_packet(), None);
+        assert!(!decoder.echo_undecided());
+        decoder.start(&ping);
+        decoder.push(&ping);
+        assert_eq!(decoder.next_packet(), None);
+
+        // An adapter that does not echo is recognised from its first reply,
+        // after which replies identical to the request are not mistaken for echoes
+        let mut decoder = PacketDecoder::new();
+        decoder.start(&ping);
+        decoder.push(&reply);
+        assert_eq!(decode_all(&mut decoder).len(), 1);
+        decoder.start(&ping);
+        decoder.push(&ping);
+        assert_eq!(decode_all(&mut decoder)[0].error, 0x01);
+    }
+}
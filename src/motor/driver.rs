@@ -5,8 +5,13 @@
 
 use tracing::{debug, info, warn};
 
-use super::feetech::{FeetechBus, FeetechError, OperatingMode, Register};
-use super::kinematics::{body_to_wheel_raw, WheelVelocities};
+use super::angle::{Angle, MoveProfile};
+use super::arm::{ArmDriver, JointFeedback};
+use super::calibration::Calibration;
+use super::feetech::{
+    BusInventory, FeetechBus, FeetechError, OperatingMode, PositionGoal, Register,
+};
+use super::kinematics::{KinematicParams, WheelVelocities, body_to_wheel_raw_with_params};
 
 /// Motor IDs for the LeKiwi base (as configured in the motors)
 pub const MOTOR_ID_LEFT: u8 = 7;
@@ -16,10 +21,55 @@ pub const MOTOR_ID_RIGHT: u8 = 9;
 /// All base motor IDs
 pub const BASE_MOTOR_IDS: [u8; 3] = [MOTOR_ID_LEFT, MOTOR_ID_BACK, MOTOR_ID_RIGHT];
 
-/// High-level motor driver for the LeKiwi omniwheel base
+/// Registers in the present-state block, read in one transaction
+const FEEDBACK_REGISTERS: [Register; 5] = [
+    Register::PresentPosition,
+    Register::PresentVelocity,
+    Register::PresentLoad,
+    Register::PresentVoltage,
+    Register::PresentTemperature,
+];
+
+/// Length of the present-state block in bytes
+const FEEDBACK_LEN: u8 = 8;
+
+/// Present state of one motor, decoded from a single register block read
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MotorFeedback {
+    pub id: u8,
+    pub position: u16,   // raw steps (0-4095)
+    pub velocity: i16,   // raw steps/s
+    pub load: i16,       // 0.1% of max torque, signed
+    pub voltage: f32,    // V
+    pub temperature: u8, // °C
+}
+
+impl MotorFeedback {
+    /// Decode the bytes read from `Register::PresentPosition` onwards
+    fn decode(id: u8, data: &[u8]) -> Self {
+        let base = Register::PresentPosition.address();
+        let [position, velocity, load, voltage, temperature] =
+            FEEDBACK_REGISTERS.map(|r| r.decode(&data[(r.address() - base) as usize..]));
+        Self {
+            id,
+            // Present position never has the sign bit set
+            position: position.unsigned_abs() as u16,
+            velocity: velocity as i16,
+            load: load as i16,
+            voltage: voltage as f32 / 10.0,
+            temperature: temperature as u8,
+        }
+    }
+}
+
+/// High-level motor driver for the LeKiwi omniwheel base and, optionally, its arm
 pub struct MotorDriver {
     bus: FeetechBus,
     motor_ids: [u8; 3], // [left, back, right]
+    kinematics: KinematicParams,
+    commanded: WheelVelocities, // last goal velocities sent
+    arm: Option<ArmDriver>,     // arm joints on the same bus
+    calibration: Calibration,
 }
 
 impl MotorDriver {
@@ -32,15 +82,50 @@ impl MotorDriver {
     pub fn with_motor_ids(port: &str, motor_ids: [u8; 3]) -> Result<Self, FeetechError> {
         info!("Opening motor bus on {}", port);
         let bus = FeetechBus::open(port)?;
-        Ok(Self { bus, motor_ids })
+        Ok(Self::with_bus(bus, motor_ids))
+    }
+
+    /// Create on top of an already opened bus (e.g. a simulated one)
+    pub fn with_bus(bus: FeetechBus, motor_ids: [u8; 3]) -> Self {
+        Self {
+            bus,
+            motor_ids,
+            kinematics: KinematicParams::default(),
+            commanded: WheelVelocities::zero(),
+            arm: None,
+            calibration: Calibration::default(),
+        }
+    }
+
+    /// Also drive the arm on this bus, initialized by `initialize`
+    pub fn set_arm(&mut self, arm: ArmDriver) {
+        self.arm = Some(arm);
+    }
+
+    /// Convert angles and velocities with this calibration from now on
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Override the kinematic parameters used by `set_body_velocity`
+    pub fn set_kinematics(&mut self, params: KinematicParams) {
+        self.kinematics = params;
     }
 
     /// Initialize the motors for velocity control
     ///
     /// This must be called before sending velocity commands.
-    /// It disables torque, sets velocity mode, and re-enables torque.
+    /// It disables torque, sets velocity mode, clears any goal velocity left
+    /// over from before (e.g. a lost connection), and re-enables torque.
     pub fn initialize(&mut self) -> Result<(), FeetechError> {
-        info!("Initializing motors {:?} for velocity control", self.motor_ids);
+        info!(
+            "Initializing motors {:?} for velocity control",
+            self.motor_ids
+        );
 
         // First, check that all motors are reachable
         for &id in &self.motor_ids {
@@ -54,19 +139,21 @@ impl MotorDriver {
             }
         }
 
-        // Disable torque on all motors (required before changing operating mode)
-        for &id in &self.motor_ids {
-            self.bus.disable_torque(id)?;
-        }
+        // Disable torque on all motors (required before changing operating mode),
+        // switch them to velocity mode and enable torque again; each step is
+        // staged and takes effect on all wheels at once
+        let ids = self.motor_ids;
+        self.bus.set_torque(&ids, false)?;
+        self.bus
+            .set_operating_modes(&ids, OperatingMode::Velocity)?;
 
-        // Set velocity mode on all motors
-        for &id in &self.motor_ids {
-            self.bus.set_operating_mode(id, OperatingMode::Velocity)?;
-        }
+        // Never resume an old goal velocity when torque comes back on
+        self.set_wheel_velocities(WheelVelocities::zero())?;
 
-        // Enable torque on all motors
-        for &id in &self.motor_ids {
-            self.bus.enable_torque(id)?;
+        self.bus.set_torque(&ids, true)?;
+
+        if let Some(arm) = &self.arm {
+            arm.initialize(&mut self.bus)?;
         }
 
         info!("Motors initialized successfully");
@@ -80,25 +167,41 @@ impl MotorDriver {
     /// * `y` - Lateral velocity in m/s (positive = left)
     /// * `theta` - Rotational velocity in deg/s (positive = counter-clockwise)
     pub fn set_body_velocity(&mut self, x: f32, y: f32, theta: f32) -> Result<(), FeetechError> {
-        let wheels = body_to_wheel_raw(x, y, theta);
+        let k = &self.kinematics;
+        let wheels =
+            body_to_wheel_raw_with_params(x, y, theta, k.wheel_radius, k.base_radius, k.max_raw);
         self.set_wheel_velocities(wheels)
     }
 
     /// Send raw wheel velocities
-    pub fn set_wheel_velocities(&mut self, velocities: WheelVelocities) -> Result<(), FeetechError> {
+    ///
+    /// Velocities are taken in the calibrated direction of each wheel.
+    pub fn set_wheel_velocities(
+        &mut self,
+        velocities: WheelVelocities,
+    ) -> Result<(), FeetechError> {
         debug!(
             "Setting wheel velocities: left={}, back={}, right={}",
             velocities.left, velocities.back, velocities.right
         );
 
         // Use sync_write for efficiency
-        let data = [
-            (self.motor_ids[0], velocities.left),
-            (self.motor_ids[1], velocities.back),
-            (self.motor_ids[2], velocities.right),
-        ];
+        let data: Vec<(u8, i32)> = self
+            .motor_ids
+            .iter()
+            .zip(velocities.as_array())
+            .map(|(&id, v)| (id, self.calibration.get(id).apply_direction(v as i32)))
+            .collect();
 
-        self.bus.sync_write_i16(Register::GoalVelocity, &data)
+        self.bus
+            .sync_write_register(Register::GoalVelocity, &data)?;
+        self.commanded = velocities;
+        Ok(())
+    }
+
+    /// Last wheel velocities successfully sent to the motors
+    pub fn commanded_velocities(&self) -> WheelVelocities {
+        self.commanded
     }
 
     /// Stop all motors immediately
@@ -108,28 +211,167 @@ impl MotorDriver {
     }
 
     /// Disable torque on all motors (allows free movement)
+    ///
+    /// Only the wheels are released; the arm keeps holding its position so it
+    /// does not drop under its own weight.
     pub fn disable_torque(&mut self) -> Result<(), FeetechError> {
-        info!("Disabling torque on all motors");
-        for &id in &self.motor_ids {
-            self.bus.disable_torque(id)?;
+        info!("Disabling torque on wheel motors");
+        let ids = self.motor_ids;
+        self.release(&ids)
+    }
+
+    /// Disable torque on the arm joints; an unsupported arm drops under its own weight
+    pub fn disable_arm_torque(&mut self) -> Result<(), FeetechError> {
+        let Some(arm) = &self.arm else {
+            return Ok(());
+        };
+        info!("Disabling torque on arm motors");
+        let ids = arm.ids();
+        self.release(&ids)
+    }
+
+    /// Release torque on all `ids` at once, or else on as many of them as answer
+    ///
+    /// A staged write changes nothing if one motor is missing, so on failure
+    /// every motor is released on its own; the first error is returned.
+    fn release(&mut self, ids: &[u8]) -> Result<(), FeetechError> {
+        let Err(e) = self.bus.set_torque(ids, false) else {
+            return Ok(());
+        };
+        warn!("Releasing motors {:?} together failed: {}", ids, e);
+        let mut first = None;
+        for &id in ids {
+            if let Err(e) = self.bus.disable_torque(id) {
+                warn!("Motor {} still has torque: {}", id, e);
+                first.get_or_insert(e);
+            }
         }
-        Ok(())
+        first.map_or(Ok(()), Err)
     }
 
     /// Read current wheel velocities
     pub fn get_wheel_velocities(&mut self) -> Result<WheelVelocities, FeetechError> {
-        let left = self.bus.get_velocity(self.motor_ids[0])?;
-        let back = self.bus.get_velocity(self.motor_ids[1])?;
-        let right = self.bus.get_velocity(self.motor_ids[2])?;
+        // One sync read transaction instead of a round trip per motor
+        let raw = self
+            .bus
+            .sync_read_register(Register::PresentVelocity, &self.motor_ids)?;
+        let [left, back, right] = [0, 1, 2].map(|i| {
+            self.calibration
+                .get(self.motor_ids[i])
+                .apply_direction(raw[i]) as i16
+        });
 
         Ok(WheelVelocities::new(left, back, right))
     }
 
+    /// Read position, velocity, load, voltage and temperature of all wheels
+    ///
+    /// Returns feedback as [left, back, right].
+    pub fn read_feedback(&mut self) -> Result<[MotorFeedback; 3], FeetechError> {
+        let data = self
+            .bus
+            .sync_read(Register::PresentPosition, FEEDBACK_LEN, &self.motor_ids)?;
+
+        let mut feedback = [MotorFeedback::default(); 3];
+        for ((fb, &id), data) in feedback.iter_mut().zip(&self.motor_ids).zip(&data) {
+            *fb = MotorFeedback::decode(id, data);
+            fb.velocity = self.calibration.get(id).apply_direction(fb.velocity as i32) as i16;
+        }
+        Ok(feedback)
+    }
+
+    /// Move the arm joints to the given positions (deg from the calibrated home)
+    ///
+    /// Does nothing when no arm is configured.
+    pub fn set_arm_positions(&mut self, degrees: [f32; 6]) -> Result<(), FeetechError> {
+        match &self.arm {
+            Some(arm) => arm.set_positions(&mut self.bus, &self.calibration, degrees),
+            None => Ok(()),
+        }
+    }
+
+    /// Read the arm joints, `None` when no arm is configured
+    pub fn read_arm_feedback(&mut self) -> Result<Option<[JointFeedback; 6]>, FeetechError> {
+        match &self.arm {
+            Some(arm) => arm
+                .read_feedback(&mut self.bus, &self.calibration)
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Switch motors to position mode, holding their present position
+    ///
+    /// For servos on the bus other than the wheels, e.g. an arm or a pan-tilt head.
+    pub fn enable_position_mode(&mut self, ids: &[u8]) -> Result<(), FeetechError> {
+        self.bus.set_torque(ids, false)?;
+        self.bus.set_operating_modes(ids, OperatingMode::Position)?;
+        let present = self
+            .bus
+            .sync_read_register(Register::PresentPosition, ids)?;
+        let hold: Vec<(u8, PositionGoal)> = ids
+            .iter()
+            .zip(present)
+            .map(|(&id, position)| (id, PositionGoal::new(position)))
+            .collect();
+        self.bus.sync_write_positions(&hold)?;
+        self.bus.set_torque(ids, true)
+    }
+
+    /// Move a position-mode motor to an angle from its calibrated home
+    pub fn move_to(
+        &mut self,
+        id: u8,
+        angle: Angle,
+        profile: MoveProfile,
+    ) -> Result<(), FeetechError> {
+        let steps = self.calibration.get(id).to_steps(angle);
+        self.bus.set_position(id, profile.goal(steps))
+    }
+
+    /// Move several position-mode motors at once, with the same profile
+    ///
+    /// The moves are staged and start together once every motor acknowledged
+    /// its goal; if one does not answer, none moves.
+    pub fn sync_move_to(
+        &mut self,
+        targets: &[(u8, Angle)],
+        profile: MoveProfile,
+    ) -> Result<(), FeetechError> {
+        let goals: Vec<(u8, PositionGoal)> = targets
+            .iter()
+            .map(|&(id, angle)| (id, profile.goal(self.calibration.get(id).to_steps(angle))))
+            .collect();
+        self.bus.staged_write_positions(&goals)
+    }
+
+    /// Present angle of a motor from its calibrated home
+    pub fn position(&mut self, id: u8) -> Result<Angle, FeetechError> {
+        Ok(self
+            .calibration
+            .get(id)
+            .to_angle(self.bus.get_position(id)?))
+    }
+
+    /// Whether a position-mode motor is still travelling to its goal
+    pub fn is_moving(&mut self, id: u8) -> Result<bool, FeetechError> {
+        self.bus.is_moving(id)
+    }
+
     /// Check if a motor is reachable
     pub fn ping(&mut self, id: u8) -> Result<bool, FeetechError> {
         self.bus.ping(id)
     }
 
+    /// Find every servo on the bus at the given baud rates
+    ///
+    /// Stops the wheels first: a sweep takes seconds, during which nothing
+    /// else is sent to the motors.
+    pub fn scan(&mut self, baud_rates: &[u32]) -> Result<BusInventory, FeetechError> {
+        self.stop()?;
+        self.bus.scan_baud_rates(baud_rates)
+    }
+
     /// Get the motor IDs
     pub fn motor_ids(&self) -> [u8; 3] {
         self.motor_ids
@@ -144,3 +386,180 @@ impl Drop for MotorDriver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::arm::ARM_MOTOR_IDS;
+    use crate::motor::calibration::{Direction, MotorCalibration};
+    use crate::motor::sim::SimServoBank;
+    use std::time::Duration;
+
+    fn sim_driver() -> (SimServoBank, MotorDriver) {
+        let bank = SimServoBank::new(&BASE_MOTOR_IDS);
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        (bank, MotorDriver::with_bus(bus, BASE_MOTOR_IDS))
+    }
+
+    #[test]
+    fn test_initialize_and_drive() {
+        let (bank, mut driver) = sim_driver();
+        driver.initialize().unwrap();
+
+        driver.set_body_velocity(0.1, 0.0, 0.0).unwrap();
+        let expected = body_to_wheel_raw_with_params(
+            0.1,
+            0.0,
+            0.0,
+            driver.kinematics.wheel_radius,
+            driver.kinematics.base_radius,
+            driver.kinematics.max_raw,
+        );
+        let measured = driver.get_wheel_velocities().unwrap();
+        assert_eq!(measured.as_array(), expected.as_array());
+        assert_eq!(bank.present_velocity(MOTOR_ID_LEFT), Some(expected.left));
+
+        driver.stop().unwrap();
+        assert_eq!(driver.get_wheel_velocities().unwrap().as_array(), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_feedback() {
+        let (_, mut driver) = sim_driver();
+        driver.initialize().unwrap();
+        driver
+            .set_wheel_velocities(WheelVelocities::new(100, -200, 300))
+            .unwrap();
+
+        let feedback = driver.read_feedback().unwrap();
+        assert_eq!(feedback.map(|f| f.id), BASE_MOTOR_IDS);
+        assert_eq!(feedback.map(|f| f.velocity), [100, -200, 300]);
+        assert_eq!(driver.commanded_velocities().as_array(), [100, -200, 300]);
+        assert!(
+            feedback
+                .iter()
+                .all(|f| f.voltage > 10.0 && f.temperature > 0)
+        );
+    }
+
+    #[test]
+    fn test_initialize_clears_previous_goal() {
+        let (bank, mut driver) = sim_driver();
+        driver.initialize().unwrap();
+        driver
+            .set_wheel_velocities(WheelVelocities::new(500, 500, 500))
+            .unwrap();
+
+        driver.initialize().unwrap();
+        assert_eq!(bank.present_velocity(MOTOR_ID_BACK), Some(0));
+    }
+
+    #[test]
+    fn test_position_control() {
+        let bank = SimServoBank::new(&[1, 2]);
+        bank.advance_clock(Duration::ZERO);
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+        driver.enable_position_mode(&[1, 2]).unwrap();
+
+        let profile = MoveProfile {
+            speed: Some(360.0),
+            ..Default::default()
+        };
+        driver
+            .move_to(1, Angle::from_degrees(30.0), profile)
+            .unwrap();
+        driver
+            .sync_move_to(&[(2, Angle::from_radians(-0.5))], MoveProfile::default())
+            .unwrap();
+        bank.advance_clock(Duration::from_millis(300));
+
+        assert!(!driver.is_moving(1).unwrap());
+        assert!((driver.position(1).unwrap().degrees() - 30.0).abs() < 0.1);
+        assert!((driver.position(2).unwrap().radians() + 0.5).abs() < 0.002);
+        assert_eq!(bank.peek(1, Register::GoalVelocity), Some(4096));
+    }
+
+    #[test]
+    fn test_calibration_is_applied() {
+        let (bank, mut driver) = sim_driver();
+        let mut calibration = Calibration::default();
+        calibration.insert(
+            MOTOR_ID_BACK,
+            MotorCalibration {
+                homing_offset: -48,
+                direction: Direction::Inverted,
+                ..Default::default()
+            },
+        );
+        driver.set_calibration(calibration);
+        driver.initialize().unwrap();
+
+        driver
+            .set_wheel_velocities(WheelVelocities::new(100, 200, 300))
+            .unwrap();
+        assert_eq!(bank.present_velocity(MOTOR_ID_BACK), Some(-200));
+        assert_eq!(
+            driver.get_wheel_velocities().unwrap().as_array(),
+            [100, 200, 300]
+        );
+        assert_eq!(driver.read_feedback().unwrap()[1].velocity, 200);
+
+        // Raw 2048 is 48 steps past home, which is negative when inverted
+        driver.stop().unwrap();
+        bank.set_position(MOTOR_ID_BACK, 2048);
+        let angle = driver.position(MOTOR_ID_BACK).unwrap().degrees();
+        assert!((angle + 48.0 / 4096.0 * 360.0).abs() < 0.01, "{}", angle);
+    }
+
+    #[test]
+    fn test_disable_torque_keeps_the_arm_holding() {
+        let bank = SimServoBank::lekiwi();
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+        driver.set_arm(ArmDriver::new(ARM_MOTOR_IDS));
+        driver.initialize().unwrap();
+
+        driver.disable_torque().unwrap();
+        for id in BASE_MOTOR_IDS {
+            assert_eq!(bank.peek(id, Register::TorqueEnable), Some(0), "id {}", id);
+        }
+        for id in ARM_MOTOR_IDS {
+            assert_eq!(bank.peek(id, Register::TorqueEnable), Some(1), "id {}", id);
+        }
+
+        driver.disable_arm_torque().unwrap();
+        for id in ARM_MOTOR_IDS {
+            assert_eq!(bank.peek(id, Register::TorqueEnable), Some(0), "id {}", id);
+        }
+    }
+
+    #[test]
+    fn test_initialize_fails_on_missing_motor() {
+        let bank = SimServoBank::new(&[MOTOR_ID_LEFT, MOTOR_ID_BACK]);
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+        assert!(matches!(
+            driver.initialize(),
+            Err(FeetechError::Timeout { id: MOTOR_ID_RIGHT })
+        ));
+    }
+
+    #[test]
+    fn test_disable_torque_releases_every_motor_that_answers() {
+        let bank = SimServoBank::new(&[MOTOR_ID_LEFT, MOTOR_ID_RIGHT]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        for id in [MOTOR_ID_LEFT, MOTOR_ID_RIGHT] {
+            bus.enable_torque(id).unwrap();
+        }
+        let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+
+        assert!(matches!(
+            driver.disable_torque(),
+            Err(FeetechError::Timeout { id: MOTOR_ID_BACK })
+        ));
+        for id in [MOTOR_ID_LEFT, MOTOR_ID_RIGHT] {
+            assert_eq!(bank.peek(id, Register::TorqueEnable), Some(0), "id {}", id);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// Safe changes to a servo's ID and baud rate
+//
+// ID and baud rate live in EEPROM: they survive power cycles, and a wrong
+// write can make a servo unreachable or put two servos on the same ID. These
+// helpers only ever act on a bus with exactly one servo answering, unlock the
+// EEPROM through `Register::Lock` for the change, check that the servo
+// answers with its new settings, and lock the EEPROM again, also when the
+// check fails.
+
+use tracing::{debug, info, warn};
+
+use super::feetech::{
+    BAUD_RATES, FeetechBus, FeetechError, MAX_MOTOR_ID, Register, baud_rate_index,
+};
+
+/// Error changing servo settings
+#[derive(Debug, thiserror::Error)]
+pub enum EepromError {
+    #[error(transparent)]
+    Feetech(#[from] FeetechError),
+
+    #[error("No servo answers at {baud_rate} baud")]
+    NoServo { baud_rate: u32 },
+
+    #[error("Servos {ids:?} answer; connect only the servo to change")]
+    MultipleServos { ids: Vec<u8> },
+
+    #[error("Invalid motor ID {id}: must be at most {MAX_MOTOR_ID}")]
+    InvalidId { id: u8 },
+
+    #[error("Unsupported baud rate {baud_rate}: expected one of {BAUD_RATES:?}")]
+    UnsupportedBaudRate { baud_rate: u32 },
+
+    #[error("Servo does not answer with its new settings: {reason}")]
+    VerifyFailed { reason: String },
+}
+
+/// ID of the only servo on the bus, refusing buses with none or several
+pub fn single_servo(bus: &mut FeetechBus) -> Result<u8, EepromError> {
+    match bus.find_ids()?.as_slice() {
+        [] => Err(EepromError::NoServo {
+            baud_rate: bus.baud_rate(),
+        }),
+        [id] => Ok(*id),
+        ids => Err(EepromError::MultipleServos { ids: ids.to_vec() }),
+    }
+}
+
+/// Give the only servo on the bus a new ID, returning its old one
+pub fn change_id(bus: &mut FeetechBus, new_id: u8) -> Result<u8, EepromError> {
+    if new_id > MAX_MOTOR_ID {
+        return Err(EepromError::InvalidId { id: new_id });
+    }
+    let old_id = single_servo(bus)?;
+    if old_id == new_id {
+        info!("Servo already has ID {}", new_id);
+        return Ok(old_id);
+    }
+
+    info!("Changing servo ID {} -> {}", old_id, new_id);
+    bus.disable_torque(old_id)?; // also unlocks the EEPROM
+    if let Err(e) = write_id(bus, old_id, new_id) {
+        lock(bus, old_id);
+        lock(bus, new_id);
+        return Err(e);
+    }
+    bus.write(new_id, Register::Lock, 1)?;
+    Ok(old_id)
+}
+
+fn write_id(bus: &mut FeetechBus, old_id: u8, new_id: u8) -> Result<(), EepromError> {
+    bus.write(old_id, Register::Id, new_id as i32)?;
+    if !bus.ping(new_id)? {
+        return Err(EepromError::VerifyFailed {
+            reason: format!("no answer at ID {}", new_id),
+        });
+    }
+    if bus.ping(old_id)? {
+        return Err(EepromError::VerifyFailed {
+            reason: format!("still answers at old ID {}", old_id),
+        });
+    }
+    Ok(())
+}
+
+/// Lock the EEPROM after a failed change, if the servo answers at `id`
+fn lock(bus: &mut FeetechBus, id: u8) {
+    match bus.write(id, Register::Lock, 1) {
+        Ok(()) => debug!("Locked the EEPROM of servo {} again", id),
+        Err(e) => warn!("Could not lock the EEPROM of servo {}: {}", id, e),
+    }
+}
+
+/// Switch the only servo on the bus to a new baud rate, returning its ID
+///
+/// The bus follows the servo to the new rate; if the servo cannot be
+/// reached there, the bus goes back to its old rate before the error returns.
+pub fn change_baud_rate(bus: &mut FeetechBus, new_baud_rate: u32) -> Result<u8, EepromError> {
+    let index = baud_rate_index(new_baud_rate).ok_or(EepromError::UnsupportedBaudRate {
+        baud_rate: new_baud_rate,
+    })?;
+    let id = single_servo(bus)?;
+    let baud_rate = bus.baud_rate();
+    if baud_rate == new_baud_rate {
+        info!("Servo {} already runs at {} baud", id, new_baud_rate);
+        return Ok(id);
+    }
+
+    info!(
+        "Changing baud rate of servo {}: {} -> {}",
+        id, baud_rate, new_baud_rate
+    );
+    bus.disable_torque(id)?; // also unlocks the EEPROM
+    if let Err(e) = write_baud_rate(bus, id, index, new_baud_rate) {
+        bus.set_baud_rate(baud_rate)?;
+        lock(bus, id);
+        return Err(e);
+    }
+    bus.write(id, Register::Lock, 1)?;
+    Ok(id)
+}
+
+fn write_baud_rate(
+    bus: &mut FeetechBus,
+    id: u8,
+    index: u8,
+    new_baud_rate: u32,
+) -> Result<(), EepromError> {
+    match bus.write(id, Register::BaudRate, index as i32) {
+        // The servo may already answer at the new rate, garbling the reply
+        Ok(()) | Err(FeetechError::Timeout { .. }) => {}
+        Err(e @ (FeetechError::Io(_) | FeetechError::Serial(_))) => return Err(e.into()),
+        Err(e) => debug!("Unreadable reply to the baud rate change: {}", e),
+    }
+
+    bus.set_baud_rate(new_baud_rate)?;
+    if !bus.ping(id)? {
+        return Err(EepromError::VerifyFailed {
+            reason: format!("no answer from ID {} at {} baud", id, new_baud_rate),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::sim::SimServoBank;
+
+    fn sim_bus(ids: &[u8]) -> (SimServoBank, FeetechBus) {
+        let bank = SimServoBank::new(ids);
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        (bank, bus)
+    }
+
+    #[test]
+    fn test_change_id() {
+        let (bank, mut bus) = sim_bus(&[1]);
+        assert_eq!(change_id(&mut bus, 8).unwrap(), 1);
+        assert_eq!(bank.ids(), vec![8]);
+        assert_eq!(bank.peek(8, Register::Lock), Some(1));
+        assert!(matches!(
+            change_id(&mut bus, 0xFE),
+            Err(EepromError::InvalidId { id: 0xFE })
+        ));
+    }
+
+    #[test]
+    fn test_refuses_several_or_no_servos() {
+        let (bank, mut bus) = sim_bus(&[1, 2]);
+        assert!(matches!(
+            change_id(&mut bus, 7),
+            Err(EepromError::MultipleServos { ids }) if ids == [1, 2]
+        ));
+        assert_eq!(bank.ids(), vec![1, 2]);
+
+        let (_, mut bus) = sim_bus(&[]);
+        assert!(matches!(
+            change_baud_rate(&mut bus, 500_000),
+            Err(EepromError::NoServo { .. })
+        ));
+    }
+
+    #[test]
+    fn test_change_baud_rate() {
+        let (bank, mut bus) = sim_bus(&[7]);
+        assert!(matches!(
+            change_baud_rate(&mut bus, 9600),
+            Err(EepromError::UnsupportedBaudRate { baud_rate: 9600 })
+        ));
+
+        assert_eq!(change_baud_rate(&mut bus, 115_200).unwrap(), 7);
+        assert_eq!(bank.peek(7, Register::BaudRate), Some(4));
+        // The bus followed the servo; a bus at the old rate no longer reaches it
+        assert!(bus.ping(7).unwrap());
+        let mut old = FeetechBus::new(Box::new(bank.transport()));
+        assert!(!old.ping(7).unwrap());
+    }
+}
@@ -3,17 +3,54 @@
 // Protocol is similar to Dynamixel Protocol 1.0:
 // Packet format: [0xFF, 0xFF, ID, Length, Instruction, Params..., Checksum]
 
+use serde::{Deserialize, Serialize};
 use serialport::{self, SerialPort};
-use std::io::{Read, Write};
-use std::time::Duration;
-use tracing::debug;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::decoder::{MAX_PARAMS, PacketDecoder, Rejection, StatusPacket};
+use super::transport::Transport;
+
+pub use super::registers::{Encoding, MemoryArea, Register, RegisterInfo};
 
 /// Default serial configuration for Feetech motors
 pub const DEFAULT_BAUDRATE: u32 = 1_000_000;
 pub const DEFAULT_TIMEOUT_MS: u64 = 100;
 
+/// Baud rates selectable with `Register::BaudRate`, by register value
+pub const BAUD_RATES: [u32; 8] = [
+    1_000_000, 500_000, 250_000, 128_000, 115_200, 76_800, 57_600, 38_400,
+];
+
+/// `Register::BaudRate` value selecting a baud rate, if supported
+pub fn baud_rate_index(baud_rate: u32) -> Option<u8> {
+    BAUD_RATES
+        .iter()
+        .position(|&b| b == baud_rate)
+        .map(|i| i as u8)
+}
+
+/// Allowance for the servo's return delay and the adapter's latency while sweeping IDs
+pub const SCAN_LATENCY: Duration = Duration::from_millis(5);
+
+/// Response timeout while sweeping IDs; absent servos cost this much each
+///
+/// A ping and its reply are 12 bytes of 10 bits on the wire, which take
+/// over 3 ms at the slowest rates; allow twice that on top of `SCAN_LATENCY`.
+pub fn scan_timeout(baud_rate: u32) -> Duration {
+    let exchange_us = 2 * 12 * 10 * 1_000_000 / u64::from(baud_rate.max(1));
+    SCAN_LATENCY + Duration::from_micros(exchange_us)
+}
+
+/// Highest ID a single motor can have (0xFE is broadcast)
+pub const MAX_MOTOR_ID: u8 = 0xFD;
+
+/// Broadcast ID: addressed to every motor on the bus
+pub const BROADCAST_ID: u8 = 0xFE;
+
 /// Packet header bytes
-const HEADER: [u8; 2] = [0xFF, 0xFF];
+pub(crate) const HEADER: [u8; 2] = [0xFF, 0xFF];
 
 /// Instruction set
 #[repr(u8)]
@@ -24,28 +61,10 @@ pub enum Instruction {
     Write = 0x03,
     RegWrite = 0x04,
     Action = 0x05,
+    SyncRead = 0x82,
     SyncWrite = 0x83,
 }
 
-/// Register addresses for STS3215
-#[repr(u8)]
-#[derive(Debug, Clone, Copy)]
-pub enum Register {
-    // EEPROM area (persists across power cycles)
-    ModelNumber = 3, // 2 bytes, read-only
-    Id = 5,          // 1 byte
-    BaudRate = 6,    // 1 byte
-
-    // RAM area (volatile)
-    OperatingMode = 33,   // 1 byte: 0=position, 1=velocity, 2=PWM, 3=step
-    TorqueEnable = 40,    // 1 byte: 0=off, 1=on
-    GoalPosition = 42,    // 2 bytes
-    GoalVelocity = 46,    // 2 bytes (signed, velocity mode)
-    Lock = 55,            // 1 byte: 0=unlocked, 1=locked
-    PresentPosition = 56, // 2 bytes, read-only
-    PresentVelocity = 58, // 2 bytes, read-only (signed)
-}
-
 /// Operating modes
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -56,6 +75,87 @@ pub enum OperatingMode {
     Step = 3,
 }
 
+/// Position-mode target for one motor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionGoal {
+    pub position: i32, // raw steps (0-4095)
+    /// Running speed (steps/s); `None` keeps the current setting, 0 = maximum
+    pub speed: Option<u16>,
+    /// Acceleration (100 steps/s²); `None` keeps the current setting, 0 = maximum
+    pub acceleration: Option<u8>,
+}
+
+impl PositionGoal {
+    /// Move to `position` with the speed and acceleration already set
+    pub fn new(position: i32) -> Self {
+        Self {
+            position,
+            speed: None,
+            acceleration: None,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: u16) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn with_acceleration(mut self, acceleration: u8) -> Self {
+        self.acceleration = Some(acceleration);
+        self
+    }
+}
+
+/// A servo found by `FeetechBus::scan`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScannedServo {
+    pub id: u8,
+    pub baud_rate: u32,
+    /// `None` if the servo answered pings but not reads (e.g. two servos sharing the ID)
+    pub model_number: Option<u16>,
+    pub firmware: Option<String>, // "major.minor"
+}
+
+/// Every servo answering on a bus, across the swept baud rates
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusInventory {
+    pub baud_rates: Vec<u32>, // swept, in order
+    pub servos: Vec<ScannedServo>,
+}
+
+/// Hardware fault reported in the status byte of a servo response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotorFault {
+    Voltage,     // bit 0: input voltage out of range
+    Sensor,      // bit 1: magnetic encoder fault
+    Overheat,    // bit 2: temperature above limit
+    Overcurrent, // bit 3: current above protection limit
+    Overload,    // bit 5: load above overload torque
+}
+
+impl MotorFault {
+    /// Every fault bit; a status byte has no others
+    pub const MASK: u8 = 0x01 | 0x02 | 0x04 | 0x08 | 0x20;
+
+    const BITS: [(u8, MotorFault); 5] = [
+        (0x01, MotorFault::Voltage),
+        (0x02, MotorFault::Sensor),
+        (0x04, MotorFault::Overheat),
+        (0x08, MotorFault::Overcurrent),
+        (0x20, MotorFault::Overload),
+    ];
+
+    /// Decode every fault flagged in a status byte
+    pub fn from_status(status: u8) -> Vec<MotorFault> {
+        Self::BITS
+            .iter()
+            .filter(|(bit, _)| status & bit != 0)
+            .map(|&(_, fault)| fault)
+            .collect()
+    }
+}
+
 /// Error types for Feetech communication
 #[derive(Debug, thiserror::Error)]
 pub enum FeetechError {
@@ -76,13 +176,25 @@ pub enum FeetechError {
 
     #[error("Timeout waiting for response from motor {id}")]
     Timeout { id: u8 },
+
+    #[error("Register {register:?} is read-only")]
+    ReadOnlyRegister { register: Register },
+
+    #[error("Value {value} out of range for register {register:?}")]
+    ValueOutOfRange { register: Register, value: i32 },
+
+    #[error("Cannot read {length} bytes at once: a reply holds at most {MAX_PARAMS}")]
+    ReadTooLong { length: u8 },
 }
 
 pub type Result<T> = std::result::Result<T, FeetechError>;
 
 /// Feetech motor bus - handles serial communication with motors
 pub struct FeetechBus {
-    port: Box<dyn SerialPort>,
+    port: Box<dyn Transport>,
+    baud_rate: u32,
+    timeout: Duration, // per response
+    decoder: PacketDecoder,
 }
 
 impl FeetechBus {
@@ -93,15 +205,51 @@ impl FeetechBus {
 
     /// Open with custom baudrate
     pub fn open_with_baudrate(port_name: &str, baudrate: u32) -> Result<Self> {
-        let port = serialport::new(port_name, baudrate)
+        let port: Box<dyn SerialPort> = serialport::new(port_name, baudrate)
             .timeout(Duration::from_millis(DEFAULT_TIMEOUT_MS))
             .open()?;
 
-        Ok(Self { port })
+        let mut bus = Self::new(Box::new(port));
+        bus.baud_rate = baudrate;
+        Ok(bus)
+    }
+
+    /// Create a bus on top of any transport (serial port, simulator, ...)
+    ///
+    /// The transport is assumed to run at `DEFAULT_BAUDRATE`.
+    pub fn new(port: Box<dyn Transport>) -> Self {
+        Self {
+            port,
+            baud_rate: DEFAULT_BAUDRATE,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            decoder: PacketDecoder::new(),
+        }
+    }
+
+    /// Baud rate the bus currently talks at
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    /// Talk to the motors at another baud rate from now on
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        debug!("Switching bus to {} baud", baud_rate);
+        self.port.set_baud_rate(baud_rate)?;
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    /// Wait at most `timeout` for each response from now on
+    ///
+    /// Shorter than `DEFAULT_TIMEOUT_MS` speeds up sweeping absent IDs.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.port.set_timeout(timeout)?;
+        self.timeout = timeout;
+        Ok(())
     }
 
     /// Calculate checksum for a packet (excluding header)
-    fn checksum(data: &[u8]) -> u8 {
+    pub(crate) fn checksum(data: &[u8]) -> u8 {
         let sum: u16 = data.iter().map(|&b| b as u16).sum();
         (!sum & 0xFF) as u8
     }
@@ -126,66 +274,124 @@ impl FeetechBus {
 
     /// Send a packet and optionally wait for response
     fn send_packet(&mut self, packet: &[u8]) -> Result<()> {
+        // Drop stale bytes from a previous timed-out exchange
+        self.port.clear_input()?;
+        self.decoder.start(packet);
         self.port.write_all(packet)?;
         self.port.flush()?;
         Ok(())
     }
 
-    /// Read a response packet
+    /// Read the next response packet, which must come from `expected_id`
+    ///
+    /// Garbage and corrupted packets on the line are skipped; the response
+    /// has the bus timeout to arrive, however it is fragmented.
     fn read_response(&mut self, expected_id: u8) -> Result<Vec<u8>> {
-        let mut header = [0u8; 2];
-        self.port.read_exact(&mut header).map_err(|e| {
-            if e.kind() == std::io::ErrorKind::TimedOut {
-                FeetechError::Timeout { id: expected_id }
-            } else {
-                FeetechError::Io(e)
+        let deadline = Instant::now() + self.timeout;
+        let packet = loop {
+            if let Some(packet) = self.decoder.next_packet() {
+                break packet;
+            }
+            let timed_out = Instant::now() >= deadline || {
+                let mut chunk = [0u8; 64];
+                match self.port.read(&mut chunk) {
+                    Ok(n) => {
+                        self.decoder.push(&chunk[..n]);
+                        false
+                    }
+                    Err(e) if e.kind() == ErrorKind::TimedOut => true,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => false,
+                    Err(e) => return Err(FeetechError::Io(e)),
+                }
+            };
+            if timed_out {
+                match self.settle_echo()? {
+                    Some(packet) => break packet,
+                    None => return Err(self.no_response(expected_id)),
+                }
             }
-        })?;
+        };
 
-        if header != HEADER {
+        if packet.id != expected_id {
+            // The expected response may be the packet dropped just before
+            if let Some(rejection) = self.decoder.take_rejected() {
+                return Err(Self::rejection_error(expected_id, rejection));
+            }
             return Err(FeetechError::InvalidResponse {
                 id: expected_id,
-                reason: format!("Invalid header: {:02X?}", header),
+                reason: format!("ID mismatch: expected {}, got {}", expected_id, packet.id),
             });
         }
+        self.decoder.take_rejected();
 
-        let mut id_length = [0u8; 2];
-        self.port.read_exact(&mut id_length)?;
-        let id = id_length[0];
-        let length = id_length[1] as usize;
-
-        if id != expected_id {
-            return Err(FeetechError::InvalidResponse {
-                id: expected_id,
-                reason: format!("ID mismatch: expected {}, got {}", expected_id, id),
+        if packet.error != 0 {
+            return Err(FeetechError::MotorError {
+                id: packet.id,
+                status: packet.error,
             });
         }
+        Ok(packet.params)
+    }
 
-        // Read remaining bytes (error + params + checksum = length bytes)
-        let mut remaining = vec![0u8; length];
-        self.port.read_exact(&mut remaining)?;
+    /// Decode a copy of the request that may be its echo or its reply
+    ///
+    /// Probes the adapter once to learn whether it echoes; `None` unless the
+    /// copy turns out to be the reply.
+    fn settle_echo(&mut self) -> Result<Option<StatusPacket>> {
+        if !self.decoder.echo_undecided() {
+            return Ok(None);
+        }
+        let echoes = self.probe_echo()?;
+        self.decoder.set_echo(echoes);
+        Ok(self.decoder.next_packet())
+    }
 
-        // Verify checksum
-        let mut checksum_data = vec![id, length as u8];
-        checksum_data.extend_from_slice(&remaining[..remaining.len() - 1]);
-        let expected_checksum = Self::checksum(&checksum_data);
-        let received_checksum = remaining[remaining.len() - 1];
+    /// Whether the adapter hands transmitted bytes back
+    ///
+    /// Sends a sync read of no servos: nobody answers it, so a copy of it
+    /// coming back can only be the echo.
+    fn probe_echo(&mut self) -> Result<bool> {
+        let probe = Self::build_packet(
+            BROADCAST_ID,
+            Instruction::SyncRead,
+            &[Register::Id.address(), 1],
+        );
+        self.port.clear_input()?;
+        self.port.write_all(&probe)?;
+        self.port.flush()?;
 
-        if expected_checksum != received_checksum {
-            return Err(FeetechError::ChecksumMismatch { id });
+        let deadline = Instant::now() + self.timeout;
+        let mut received = Vec::new();
+        while received.len() < probe.len() && Instant::now() < deadline {
+            let mut chunk = [0u8; 64];
+            match self.port.read(&mut chunk) {
+                Ok(n) => received.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::TimedOut => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(FeetechError::Io(e)),
+            }
         }
+        Ok(received.starts_with(&probe))
+    }
 
-        // Check error status
-        let error_status = remaining[0];
-        if error_status != 0 {
-            return Err(FeetechError::MotorError {
-                id,
-                status: error_status,
-            });
+    /// Error for a response that did not arrive intact
+    ///
+    /// A timeout, unless a corrupted packet was dropped on the way.
+    fn no_response(&mut self, expected_id: u8) -> FeetechError {
+        match self.decoder.take_rejected() {
+            Some(rejection) => Self::rejection_error(expected_id, rejection),
+            None => FeetechError::Timeout { id: expected_id },
         }
+    }
 
-        // Return parameters (excluding error byte and checksum)
-        Ok(remaining[1..remaining.len() - 1].to_vec())
+    fn rejection_error(expected_id: u8, rejection: Rejection) -> FeetechError {
+        match rejection {
+            Rejection::Checksum { id } => FeetechError::ChecksumMismatch { id },
+            Rejection::Length { id, length } => FeetechError::InvalidResponse {
+                id: expected_id,
+                reason: format!("Invalid length {} in packet from {}", length, id),
+            },
+        }
     }
 
     /// Ping a motor to check if it's connected
@@ -200,27 +406,147 @@ impl FeetechBus {
         }
     }
 
-    /// Write a single byte to a register
-    pub fn write_u8(&mut self, id: u8, register: Register, value: u8) -> Result<()> {
-        let params = [register as u8, value];
+    /// IDs of every servo answering a ping at the current baud rate
+    ///
+    /// Sweeps IDs 0-253 with `scan_timeout` of the baud rate. A garbled answer
+    /// (e.g. two servos sharing an ID) still counts as an answer.
+    pub fn find_ids(&mut self) -> Result<Vec<u8>> {
+        let previous = self.timeout;
+        self.set_timeout(scan_timeout(self.baud_rate))?;
+        let mut ids = Vec::new();
+        let mut result = Ok(());
+        for id in 0..=MAX_MOTOR_ID {
+            match self.ping(id) {
+                Ok(true) => ids.push(id),
+                Ok(false) => {}
+                Err(e @ (FeetechError::Io(_) | FeetechError::Serial(_))) => {
+                    result = Err(e);
+                    break;
+                }
+                Err(e) => {
+                    warn!("Garbled answer from ID {}: {}", id, e);
+                    ids.push(id);
+                }
+            }
+        }
+        // Back to the caller's timeout, also after an error
+        let restored = self.set_timeout(previous);
+        result.and(restored).map(|()| ids)
+    }
+
+    /// Find every servo on the bus at every supported baud rate
+    pub fn scan(&mut self) -> Result<BusInventory> {
+        self.scan_baud_rates(&BAUD_RATES)
+    }
+
+    /// Find every servo on the bus at the given baud rates
+    ///
+    /// Reads model number and firmware version of each responder. The bus
+    /// returns to its current baud rate afterwards, also on errors.
+    pub fn scan_baud_rates(&mut self, baud_rates: &[u32]) -> Result<BusInventory> {
+        let original = self.baud_rate;
+        let mut inventory = BusInventory {
+            baud_rates: baud_rates.to_vec(),
+            servos: Vec::new(),
+        };
+        let mut result = Ok(());
+        for &baud_rate in baud_rates {
+            if let Err(e) = self.scan_at(baud_rate, &mut inventory.servos) {
+                result = Err(e);
+                break;
+            }
+        }
+        self.set_baud_rate(original)?;
+        result.map(|()| inventory)
+    }
+
+    fn scan_at(&mut self, baud_rate: u32, servos: &mut Vec<ScannedServo>) -> Result<()> {
+        self.set_baud_rate(baud_rate)?;
+        for id in self.find_ids()? {
+            // Firmware major, minor, (reserved), model number
+            let info = match self.read_bytes(id, Register::FirmwareMajor, 5) {
+                Ok(bytes) => Some(bytes),
+                Err(e @ (FeetechError::Io(_) | FeetechError::Serial(_))) => return Err(e),
+                Err(e) => {
+                    warn!(
+                        "Servo {} at {} baud answers pings only: {}",
+                        id, baud_rate, e
+                    );
+                    None
+                }
+            };
+            servos.push(ScannedServo {
+                id,
+                baud_rate,
+                model_number: info
+                    .as_ref()
+                    .map(|b| Register::ModelNumber.decode(&b[3..]) as u16),
+                firmware: info.as_ref().map(|b| format!("{}.{}", b[0], b[1])),
+            });
+        }
+        Ok(())
+    }
+
+    /// Read `length` consecutive bytes starting at a register
+    ///
+    /// Lets callers fetch a whole block (e.g. present position through
+    /// temperature) in a single round trip.
+    pub fn read_bytes(&mut self, id: u8, register: Register, length: u8) -> Result<Vec<u8>> {
+        Self::check_read_length(length)?;
+        let params = [register as u8, length]; // address, length
+        let packet = Self::build_packet(id, Instruction::Read, &params);
+        self.send_packet(&packet)?;
+
+        let response = self.read_response(id)?;
+        if response.len() != length as usize {
+            return Err(FeetechError::InvalidResponse {
+                id,
+                reason: format!("Expected {} bytes, got {}", length, response.len()),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Read a register as a typed value, using its width and encoding
+    pub fn read(&mut self, id: u8, register: Register) -> Result<i32> {
+        let data = self.read_bytes(id, register, register.width())?;
+        Ok(register.decode(&data))
+    }
+
+    /// Write a typed value to a register, using its width and encoding
+    ///
+    /// Fails without touching the bus if the register is read-only or the
+    /// value does not fit.
+    pub fn write(&mut self, id: u8, register: Register, value: i32) -> Result<()> {
+        Self::check_writable(register, value)?;
+        debug!("Write to motor {}: reg={:?}, value={}", id, register, value);
+        self.write_bytes(id, register, &register.encode(value))
+    }
+
+    /// Write raw bytes starting at a register
+    fn write_bytes(&mut self, id: u8, register: Register, data: &[u8]) -> Result<()> {
+        let mut params = vec![register.address()];
+        params.extend_from_slice(data);
+
         let packet = Self::build_packet(id, Instruction::Write, &params);
-        debug!(
-            "Write u8 to motor {}: reg={:?}, value={}",
-            id, register, value
-        );
         self.send_packet(&packet)?;
 
-        // Read status response
         let _ = self.read_response(id)?;
         Ok(())
     }
 
-    /// Write two bytes (little-endian) to a register
-    pub fn write_u16(&mut self, id: u8, register: Register, value: u16) -> Result<()> {
-        let params = [register as u8, (value & 0xFF) as u8, (value >> 8) as u8];
-        let packet = Self::build_packet(id, Instruction::Write, &params);
+    /// Stage a register write on one motor, applied on the next `action`
+    ///
+    /// The motor acknowledges the staged write but keeps its old value until
+    /// then. A motor holds one staged write; staging another replaces it.
+    pub fn reg_write(&mut self, id: u8, register: Register, value: i32) -> Result<()> {
+        Self::check_writable(register, value)?;
+        let mut params = vec![register.address()];
+        params.extend(register.encode(value));
+
+        let packet = Self::build_packet(id, Instruction::RegWrite, &params);
         debug!(
-            "Write u16 to motor {}: reg={:?}, value={}",
+            "Reg write to motor {}: reg={:?}, value={}",
             id, register, value
         );
         self.send_packet(&packet)?;
@@ -229,74 +555,144 @@ impl FeetechBus {
         Ok(())
     }
 
+    /// Apply the staged writes of every motor at once (broadcast, no reply)
+    pub fn action(&mut self) -> Result<()> {
+        let packet = Self::build_packet(BROADCAST_ID, Instruction::Action, &[]);
+        debug!("Action");
+        self.send_packet(&packet)
+    }
+
+    /// Write a register on several motors, taking effect on all at the same time
+    ///
+    /// Unlike `sync_write_register`, every motor acknowledges its value before
+    /// any of them applies it: if one does not answer, none changes.
+    pub fn staged_write(&mut self, register: Register, data: &[(u8, i32)]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        for (i, &(id, value)) in data.iter().enumerate() {
+            if let Err(e) = self.reg_write(id, register, value) {
+                // The failed motor may have staged the write and only lost its reply
+                self.unstage(register, &data[..=i]);
+                return Err(e);
+            }
+        }
+        self.action()
+    }
+
+    /// Replace staged writes with the present values, so a later `action` changes nothing
+    fn unstage(&mut self, register: Register, data: &[(u8, i32)]) {
+        for &(id, _) in data {
+            let result = self
+                .read(id, register)
+                .and_then(|present| self.reg_write(id, register, present));
+            if let Err(e) = result {
+                warn!("Motor {} keeps a staged {:?} write: {}", id, register, e);
+            }
+        }
+    }
+
+    /// Read a register as a typed value from several motors (one sync read)
+    pub fn sync_read_register(&mut self, register: Register, ids: &[u8]) -> Result<Vec<i32>> {
+        let data = self.sync_read(register, register.width(), ids)?;
+        Ok(data.iter().map(|d| register.decode(d)).collect())
+    }
+
+    /// Write a typed value to the same register on several motors (one sync write)
+    pub fn sync_write_register(&mut self, register: Register, data: &[(u8, i32)]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        for &(_, value) in data {
+            Self::check_writable(register, value)?;
+        }
+        let encoded: Vec<(u8, Vec<u8>)> = data
+            .iter()
+            .map(|&(id, value)| (id, register.encode(value)))
+            .collect();
+        self.sync_write_bytes(register, register.width(), &encoded)
+    }
+
+    /// Sync write raw bytes, `length` per motor, starting at a register
+    fn sync_write_bytes(
+        &mut self,
+        register: Register,
+        length: u8,
+        data: &[(u8, Vec<u8>)],
+    ) -> Result<()> {
+        // Sync write format: [start_addr, data_length, id1, data1..., id2, data2..., ...]
+        let mut params = vec![register.address(), length];
+        for (id, bytes) in data {
+            params.push(*id);
+            params.extend_from_slice(bytes);
+        }
+
+        let packet = Self::build_packet(BROADCAST_ID, Instruction::SyncWrite, &params);
+        debug!("Sync write to {} motors: reg={:?}", data.len(), register);
+        self.send_packet(&packet)
+    }
+
+    // === Raw accessors, kept for existing callers ===
+
+    /// Write a single byte to a register
+    #[deprecated(note = "use `write`, which checks the register width and range")]
+    pub fn write_u8(&mut self, id: u8, register: Register, value: u8) -> Result<()> {
+        self.write_bytes(id, register, &[value])
+    }
+
+    /// Write two bytes (little-endian) to a register
+    #[deprecated(note = "use `write`, which checks the register width and range")]
+    pub fn write_u16(&mut self, id: u8, register: Register, value: u16) -> Result<()> {
+        self.write_bytes(id, register, &value.to_le_bytes())
+    }
+
     /// Write a signed 16-bit value (for velocity)
+    #[deprecated(note = "use `write`, which encodes signed registers")]
     pub fn write_i16(&mut self, id: u8, register: Register, value: i16) -> Result<()> {
-        // Feetech uses sign-magnitude encoding for velocity:
-        // Bit 15 = direction (1 = negative), Bits 0-14 = magnitude
         let raw = encode_sign_magnitude(value);
-        self.write_u16(id, register, raw)
+        self.write_bytes(id, register, &raw.to_le_bytes())
     }
 
     /// Read a single byte from a register
+    #[deprecated(note = "use `read`, which decodes the register by its width")]
     pub fn read_u8(&mut self, id: u8, register: Register) -> Result<u8> {
-        let params = [register as u8, 1]; // address, length
-        let packet = Self::build_packet(id, Instruction::Read, &params);
-        self.send_packet(&packet)?;
-
-        let response = self.read_response(id)?;
-        if response.is_empty() {
-            return Err(FeetechError::InvalidResponse {
-                id,
-                reason: "Empty response".to_string(),
-            });
-        }
-        Ok(response[0])
+        Ok(self.read_bytes(id, register, 1)?[0])
     }
 
     /// Read two bytes (little-endian) from a register
+    #[deprecated(note = "use `read`, which decodes the register by its width")]
     pub fn read_u16(&mut self, id: u8, register: Register) -> Result<u16> {
-        let params = [register as u8, 2]; // address, length
-        let packet = Self::build_packet(id, Instruction::Read, &params);
-        self.send_packet(&packet)?;
+        let data = self.read_bytes(id, register, 2)?;
+        Ok(u16::from_le_bytes([data[0], data[1]]))
+    }
 
-        let response = self.read_response(id)?;
-        if response.len() < 2 {
-            return Err(FeetechError::InvalidResponse {
-                id,
-                reason: format!("Expected 2 bytes, got {}", response.len()),
-            });
-        }
-        Ok(u16::from_le_bytes([response[0], response[1]]))
+    /// Sync read signed 16-bit values (for velocities)
+    #[deprecated(note = "use `sync_read_register`, which decodes signed registers")]
+    pub fn sync_read_i16(&mut self, register: Register, ids: &[u8]) -> Result<Vec<i16>> {
+        let data = self.sync_read(register, 2, ids)?;
+        Ok(data
+            .iter()
+            .map(|d| decode_sign_magnitude(u16::from_le_bytes([d[0], d[1]])))
+            .collect())
     }
 
     /// Sync write: write same register to multiple motors efficiently
     /// data: [(id, value), ...]
+    #[deprecated(note = "use `sync_write_register`, which checks the register width and range")]
     pub fn sync_write_u16(&mut self, register: Register, data: &[(u8, u16)]) -> Result<()> {
         if data.is_empty() {
             return Ok(());
         }
-
-        // Sync write format:
-        // [start_addr, data_length, id1, data1_lo, data1_hi, id2, data2_lo, data2_hi, ...]
-        let data_length: u8 = 2; // 2 bytes per motor
-        let mut params = vec![register as u8, data_length];
-
-        for &(id, value) in data {
-            params.push(id);
-            params.push((value & 0xFF) as u8);
-            params.push((value >> 8) as u8);
-        }
-
-        // Broadcast ID for sync write
-        let packet = Self::build_packet(0xFE, Instruction::SyncWrite, &params);
-        debug!("Sync write to {} motors: reg={:?}", data.len(), register);
-        self.send_packet(&packet)?;
-
-        // Sync write has no response
-        Ok(())
+        let encoded: Vec<(u8, Vec<u8>)> = data
+            .iter()
+            .map(|&(id, value)| (id, value.to_le_bytes().to_vec()))
+            .collect();
+        self.sync_write_bytes(register, 2, &encoded)
     }
 
     /// Sync write signed 16-bit values (for velocities)
+    #[deprecated(note = "use `sync_write_register`, which encodes signed registers")]
+    #[allow(deprecated)]
     pub fn sync_write_i16(&mut self, register: Register, data: &[(u8, i16)]) -> Result<()> {
         let encoded: Vec<(u8, u16)> = data
             .iter()
@@ -305,34 +701,168 @@ impl FeetechBus {
         self.sync_write_u16(register, &encoded)
     }
 
+    fn check_writable(register: Register, value: i32) -> Result<()> {
+        if register.is_read_only() {
+            return Err(FeetechError::ReadOnlyRegister { register });
+        }
+        if !register.check_range(value) {
+            return Err(FeetechError::ValueOutOfRange { register, value });
+        }
+        Ok(())
+    }
+
+    /// Refuse reads whose reply the decoder would throw away as too long
+    fn check_read_length(length: u8) -> Result<()> {
+        if length as usize > MAX_PARAMS {
+            return Err(FeetechError::ReadTooLong { length });
+        }
+        Ok(())
+    }
+
+    /// Sync read: read the same register span from multiple motors in one transaction
+    ///
+    /// Every motor answers with its own status packet, in the order of `ids`.
+    /// Returns the data of each motor in that same order.
+    pub fn sync_read(
+        &mut self,
+        register: Register,
+        length: u8,
+        ids: &[u8],
+    ) -> Result<Vec<Vec<u8>>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        Self::check_read_length(length)?;
+
+        // Sync read format: [start_addr, data_length, id1, id2, ...]
+        let mut params = vec![register as u8, length];
+        params.extend_from_slice(ids);
+
+        let packet = Self::build_packet(BROADCAST_ID, Instruction::SyncRead, &params);
+        debug!(
+            "Sync read from {} motors: reg={:?}, len={}",
+            ids.len(),
+            register,
+            length
+        );
+        self.send_packet(&packet)?;
+
+        let mut results = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let response = self.read_response(id)?;
+            if response.len() != length as usize {
+                return Err(FeetechError::InvalidResponse {
+                    id,
+                    reason: format!("Expected {} bytes, got {}", length, response.len()),
+                });
+            }
+            results.push(response);
+        }
+        Ok(results)
+    }
+
     // === High-level convenience methods ===
 
     /// Enable torque on a motor
     pub fn enable_torque(&mut self, id: u8) -> Result<()> {
-        self.write_u8(id, Register::TorqueEnable, 1)?;
-        self.write_u8(id, Register::Lock, 1)
+        self.write(id, Register::TorqueEnable, 1)?;
+        self.write(id, Register::Lock, 1)
     }
 
     /// Disable torque on a motor
     pub fn disable_torque(&mut self, id: u8) -> Result<()> {
-        self.write_u8(id, Register::TorqueEnable, 0)?;
-        self.write_u8(id, Register::Lock, 0)
+        self.write(id, Register::TorqueEnable, 0)?;
+        self.write(id, Register::Lock, 0)
     }
 
     /// Set operating mode (must disable torque first)
     pub fn set_operating_mode(&mut self, id: u8, mode: OperatingMode) -> Result<()> {
-        self.write_u8(id, Register::OperatingMode, mode as u8)
+        self.write(id, Register::OperatingMode, mode as i32)
+    }
+
+    /// Enable or disable torque on several motors at the same time
+    ///
+    /// Locks or unlocks the EEPROM along with it, like `enable_torque` and
+    /// `disable_torque`.
+    pub fn set_torque(&mut self, ids: &[u8], enabled: bool) -> Result<()> {
+        let data: Vec<(u8, i32)> = ids.iter().map(|&id| (id, enabled as i32)).collect();
+        self.staged_write(Register::TorqueEnable, &data)?;
+        self.staged_write(Register::Lock, &data)
+    }
+
+    /// Switch several motors to an operating mode at the same time (torque must be off)
+    pub fn set_operating_modes(&mut self, ids: &[u8], mode: OperatingMode) -> Result<()> {
+        let data: Vec<(u8, i32)> = ids.iter().map(|&id| (id, mode as i32)).collect();
+        self.staged_write(Register::OperatingMode, &data)
     }
 
     /// Set goal velocity for a motor (must be in velocity mode)
     pub fn set_velocity(&mut self, id: u8, velocity: i16) -> Result<()> {
-        self.write_i16(id, Register::GoalVelocity, velocity)
+        self.write(id, Register::GoalVelocity, velocity as i32)
     }
 
     /// Read present velocity from a motor
     pub fn get_velocity(&mut self, id: u8) -> Result<i16> {
-        let raw = self.read_u16(id, Register::PresentVelocity)?;
-        Ok(decode_sign_magnitude(raw))
+        Ok(self.read(id, Register::PresentVelocity)? as i16)
+    }
+
+    /// Move a motor to a goal position (must be in position mode)
+    ///
+    /// Speed and acceleration, when given, are written before the position so
+    /// the move starts with them.
+    pub fn set_position(&mut self, id: u8, goal: PositionGoal) -> Result<()> {
+        if let Some(acceleration) = goal.acceleration {
+            self.write(id, Register::Acceleration, acceleration as i32)?;
+        }
+        if let Some(speed) = goal.speed {
+            // Goal velocity doubles as the running speed in position mode
+            self.write(id, Register::GoalVelocity, speed as i32)?;
+        }
+        self.write(id, Register::GoalPosition, goal.position)
+    }
+
+    /// Read present position from a motor (raw steps)
+    pub fn get_position(&mut self, id: u8) -> Result<i32> {
+        self.read(id, Register::PresentPosition)
+    }
+
+    /// Whether a motor is still travelling to its goal
+    pub fn is_moving(&mut self, id: u8) -> Result<bool> {
+        Ok(self.read(id, Register::Moving)? != 0)
+    }
+
+    /// Move several motors at once (one sync write per register)
+    ///
+    /// Speeds and accelerations are only written for the motors that set them.
+    pub fn sync_write_positions(&mut self, goals: &[(u8, PositionGoal)]) -> Result<()> {
+        self.sync_write_profiles(goals)?;
+        let positions: Vec<(u8, i32)> = goals.iter().map(|&(id, g)| (id, g.position)).collect();
+        self.sync_write_register(Register::GoalPosition, &positions)
+    }
+
+    /// Move several motors, starting them at the same time
+    ///
+    /// Like `sync_write_positions`, but the goal positions are staged and only
+    /// triggered once every motor acknowledged its goal.
+    pub fn staged_write_positions(&mut self, goals: &[(u8, PositionGoal)]) -> Result<()> {
+        self.sync_write_profiles(goals)?;
+        let positions: Vec<(u8, i32)> = goals.iter().map(|&(id, g)| (id, g.position)).collect();
+        self.staged_write(Register::GoalPosition, &positions)
+    }
+
+    /// Write the speeds and accelerations that goals set
+    fn sync_write_profiles(&mut self, goals: &[(u8, PositionGoal)]) -> Result<()> {
+        let accelerations: Vec<(u8, i32)> = goals
+            .iter()
+            .filter_map(|&(id, g)| Some((id, g.acceleration? as i32)))
+            .collect();
+        let speeds: Vec<(u8, i32)> = goals
+            .iter()
+            .filter_map(|&(id, g)| Some((id, g.speed? as i32)))
+            .collect();
+
+        self.sync_write_register(Register::Acceleration, &accelerations)?;
+        self.sync_write_register(Register::GoalVelocity, &speeds)
     }
 }
 
@@ -342,7 +872,7 @@ fn encode_sign_magnitude(value: i16) -> u16 {
     if value >= 0 {
         value as u16
     } else {
-        (0x8000 | (-value as u16)) & 0xFFFF
+        0x8000 | value.unsigned_abs()
     }
 }
 
@@ -359,6 +889,9 @@ fn decode_sign_magnitude(raw: u16) -> i16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::motor::sim::{STS3215_MODEL, SimServoBank};
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
 
     #[test]
     fn test_checksum() {
@@ -382,6 +915,289 @@ mod tests {
         assert_eq!(decode_sign_magnitude(0x8001), -1);
     }
 
+    #[test]
+    fn test_load_decoding() {
+        let load = |raw: u16| Register::PresentLoad.decode(&raw.to_le_bytes());
+        assert_eq!(load(0), 0);
+        assert_eq!(load(500), 500);
+        assert_eq!(load(0x400 | 250), -250);
+    }
+
+    #[test]
+    fn test_write_rejects_read_only_and_out_of_range() {
+        let mut bus = FeetechBus::new(Box::new(SimServoBank::new(&[7]).transport()));
+        assert!(matches!(
+            bus.write(7, Register::PresentPosition, 0),
+            Err(FeetechError::ReadOnlyRegister { .. })
+        ));
+        assert!(matches!(
+            bus.write(7, Register::TorqueEnable, 256),
+            Err(FeetechError::ValueOutOfRange { value: 256, .. })
+        ));
+        assert!(matches!(
+            bus.sync_write_register(Register::GoalVelocity, &[(7, 0), (8, 40_000)]),
+            Err(FeetechError::ValueOutOfRange { value: 40_000, .. })
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_raw_accessors_match_typed_registers() {
+        let bank = SimServoBank::new(&[7, 8]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        bus.write_u8(7, Register::OperatingMode, OperatingMode::Velocity as u8)
+            .unwrap();
+        assert_eq!(bus.read_u8(7, Register::OperatingMode).unwrap(), 1);
+
+        bus.write_i16(7, Register::GoalVelocity, -100).unwrap();
+        assert_eq!(bus.read(7, Register::GoalVelocity).unwrap(), -100);
+        assert_eq!(bus.read_u16(7, Register::GoalVelocity).unwrap(), 0x8064);
+
+        bus.sync_write_i16(Register::GoalVelocity, &[(7, 50), (8, -50)])
+            .unwrap();
+        assert_eq!(
+            bus.sync_read_i16(Register::GoalVelocity, &[7, 8]).unwrap(),
+            vec![50, -50]
+        );
+        bus.sync_write_u16(Register::GoalVelocity, &[(8, 0x8001)])
+            .unwrap();
+        assert_eq!(bus.read(8, Register::GoalVelocity).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_scan_finds_servos_at_every_baud_rate() {
+        let bank = SimServoBank::new(&[1, 7]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        bus.disable_torque(7).unwrap();
+        bus.write(7, Register::BaudRate, 4).unwrap(); // 115200
+        let timeout = Duration::from_millis(50);
+        bus.set_timeout(timeout).unwrap();
+
+        let inventory = bus.scan().unwrap();
+        assert_eq!(inventory.baud_rates, BAUD_RATES);
+        let found: Vec<(u8, u32)> = inventory
+            .servos
+            .iter()
+            .map(|s| (s.id, s.baud_rate))
+            .collect();
+        assert_eq!(found, vec![(1, 1_000_000), (7, 115_200)]);
+        assert_eq!(inventory.servos[1].model_number, Some(STS3215_MODEL));
+        assert_eq!(inventory.servos[1].firmware.as_deref(), Some("3.10"));
+
+        // Back at the original rate and timeout
+        assert_eq!(bus.baud_rate(), DEFAULT_BAUDRATE);
+        assert_eq!(bus.timeout, timeout);
+        assert!(bus.ping(1).unwrap());
+        assert!(bus.scan_baud_rates(&[500_000]).unwrap().servos.is_empty());
+        bank.set_connected(false);
+        assert!(bus.find_ids().is_err());
+        assert_eq!(bus.timeout, timeout);
+
+        // Slow rates get time for the ping and reply to cross the wire
+        assert_eq!(scan_timeout(1_000_000), Duration::from_micros(5240));
+        assert_eq!(scan_timeout(38_400), Duration::from_micros(11250));
+    }
+
+    #[test]
+    fn test_staged_write_applies_on_action() {
+        let bank = SimServoBank::new(&[1, 2, 3]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+
+        bus.reg_write(1, Register::TorqueEnable, 1).unwrap();
+        bus.reg_write(2, Register::TorqueEnable, 1).unwrap();
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(0));
+        bus.action().unwrap();
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(1));
+        assert_eq!(bank.peek(2, Register::TorqueEnable), Some(1));
+        assert_eq!(bank.peek(3, Register::TorqueEnable), Some(0));
+
+        // A missing motor aborts the whole write, also on a later action
+        let result = bus.staged_write(Register::TorqueEnable, &[(1, 0), (3, 0), (4, 0)]);
+        assert!(matches!(result, Err(FeetechError::Timeout { id: 4 })));
+        bus.action().unwrap();
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(1));
+
+        bus.staged_write(Register::TorqueEnable, &[(1, 0), (2, 0)])
+            .unwrap();
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(0));
+        assert_eq!(bank.peek(2, Register::TorqueEnable), Some(0));
+    }
+
+    #[test]
+    fn test_position_mode() {
+        let bank = SimServoBank::new(&[1, 2]);
+        bank.advance_clock(Duration::ZERO);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        for id in [1, 2] {
+            bus.set_operating_mode(id, OperatingMode::Position).unwrap();
+            bus.enable_torque(id).unwrap();
+        }
+
+        bus.set_position(
+            1,
+            PositionGoal::new(2548)
+                .with_speed(1000)
+                .with_acceleration(50),
+        )
+        .unwrap();
+        assert_eq!(bank.peek(1, Register::GoalVelocity), Some(1000));
+        assert_eq!(bank.peek(1, Register::Acceleration), Some(50));
+        bank.advance_clock(Duration::from_millis(100));
+        // 500 steps at 1000 steps/s take half a second
+        assert!(bus.is_moving(1).unwrap());
+        let position = bus.get_position(1).unwrap();
+        assert!((2147..=2149).contains(&position), "{}", position);
+
+        bus.sync_write_positions(&[
+            (1, PositionGoal::new(1000).with_speed(0)),
+            (2, PositionGoal::new(3000)),
+        ])
+        .unwrap();
+        assert_eq!(bank.peek(1, Register::GoalVelocity), Some(0));
+        bank.advance_clock(Duration::from_millis(500));
+        assert_eq!(bus.get_position(1).unwrap(), 1000);
+        assert_eq!(bus.get_position(2).unwrap(), 3000);
+        assert!(!bus.is_moving(2).unwrap());
+    }
+
+    /// Plays back a fixed byte stream one byte per read, ignoring what is written
+    struct Scripted(VecDeque<u8>);
+
+    impl Read for Scripted {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                None => Err(ErrorKind::TimedOut.into()),
+            }
+        }
+    }
+
+    impl Write for Scripted {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Transport for Scripted {
+        fn clear_input(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn set_baud_rate(&mut self, _baud_rate: u32) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn set_timeout(&mut self, _timeout: Duration) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn scripted_bus(stream: &[u8]) -> FeetechBus {
+        FeetechBus::new(Box::new(Scripted(stream.iter().copied().collect())))
+    }
+
+    #[test]
+    fn test_reads_through_noise_and_fragments() {
+        // Noise, a zero length byte, then the reply to a read of ID 7, one byte at a time
+        let mut stream = vec![0x00, 0xFF, 0x55, 0xFF, 0xFF, 7, 0];
+        let mut reply = vec![0xFF, 0xFF, 7, 4, 0, 0x34, 0x12];
+        reply.push(FeetechBus::checksum(&reply[2..]));
+        stream.extend(&reply);
+        let mut bus = scripted_bus(&stream);
+        assert_eq!(bus.read(7, Register::PresentPosition).unwrap(), 0x1234);
+
+        // Once the stream runs dry, a dropped packet is reported instead of a timeout
+        *reply.last_mut().unwrap() ^= 0x01;
+        let mut bus = scripted_bus(&reply);
+        assert!(matches!(
+            bus.read(7, Register::PresentPosition),
+            Err(FeetechError::ChecksumMismatch { id: 7 })
+        ));
+        let mut bus = scripted_bus(&[0xFF, 0xFF, 7, 0]);
+        assert!(matches!(
+            bus.ping(7),
+            Err(FeetechError::InvalidResponse { id: 7, .. })
+        ));
+        let mut bus = scripted_bus(&[0x00, 0x13]);
+        assert!(!bus.ping(7).unwrap());
+    }
+
+    #[test]
+    fn test_reply_identical_to_the_request() {
+        // A ping answered with the voltage fault bit, on an adapter that does not echo
+        let ping = FeetechBus::build_packet(7, Instruction::Ping, &[]);
+        let mut bus = scripted_bus(&ping);
+        assert!(matches!(
+            bus.ping(7),
+            Err(FeetechError::MotorError {
+                id: 7,
+                status: 0x01
+            })
+        ));
+
+        // An echoing adapter copies the ping to an absent servo, and the probe
+        let bank = SimServoBank::new(&[7]);
+        bank.set_echo(true);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        assert!(!bus.ping(10).unwrap());
+        assert!(bus.ping(7).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_reads_longer_than_a_reply() {
+        let bank = SimServoBank::new(&[7]);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        assert!(matches!(
+            bus.read_bytes(7, Register::FirmwareMajor, 129),
+            Err(FeetechError::ReadTooLong { length: 129 })
+        ));
+        assert!(matches!(
+            bus.sync_read(Register::FirmwareMajor, 200, &[7]),
+            Err(FeetechError::ReadTooLong { length: 200 })
+        ));
+        assert_eq!(
+            bus.read_bytes(7, Register::FirmwareMajor, 128)
+                .unwrap()
+                .len(),
+            128
+        );
+    }
+
+    #[test]
+    fn test_echoing_adapter() {
+        let bank = SimServoBank::new(&[7, 8, 9]);
+        bank.set_echo(true);
+        let mut bus = FeetechBus::new(Box::new(bank.transport()));
+        assert!(bus.ping(7).unwrap());
+        assert!(!bus.ping(10).unwrap());
+        assert_eq!(
+            bus.read(8, Register::ModelNumber).unwrap(),
+            STS3215_MODEL as i32
+        );
+        bus.sync_write_register(Register::GoalPosition, &[(7, 1000), (8, 1000)])
+            .unwrap();
+        let positions = bus
+            .sync_read_register(Register::GoalPosition, &[7, 8, 9])
+            .unwrap();
+        assert_eq!(positions, vec![1000, 1000, 0]);
+    }
+
+    #[test]
+    fn test_motor_fault_bits() {
+        assert!(MotorFault::from_status(0).is_empty());
+        assert_eq!(
+            MotorFault::from_status(0x24),
+            vec![MotorFault::Overheat, MotorFault::Overload]
+        );
+    }
+
     #[test]
     fn test_build_packet() {
         let packet = FeetechBus::build_packet(1, Instruction::Ping, &[]);
@@ -393,4 +1209,15 @@ mod tests {
         assert_eq!(packet[3], 2); // Length (instruction + checksum)
         assert_eq!(packet[4], 0x01); // PING instruction
     }
+
+    #[test]
+    fn test_build_sync_read_packet() {
+        let packet =
+            FeetechBus::build_packet(BROADCAST_ID, Instruction::SyncRead, &[56, 2, 7, 8, 9]);
+        assert_eq!(packet[2], 0xFE); // broadcast
+        assert_eq!(packet[3], 7); // 5 params + instruction + checksum
+        assert_eq!(packet[4], 0x82);
+        assert_eq!(&packet[5..10], &[56, 2, 7, 8, 9]);
+        assert_eq!(packet[10], FeetechBus::checksum(&packet[2..10]));
+    }
 }
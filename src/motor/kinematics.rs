@@ -1,6 +1,9 @@
-// Omniwheel inverse kinematics for LeKiwi 3-wheel base
-// Converts body-frame velocities (x, y, theta) to individual wheel velocities.
+// Omniwheel kinematics for LeKiwi 3-wheel base
+// Converts body-frame velocities (x, y, theta) to individual wheel velocities
+// (inverse kinematics) and measured wheel velocities back to body motion
+// (forward kinematics).
 
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
 /// Wheel configuration for LeKiwi base
@@ -16,7 +19,26 @@ const STEPS_PER_REVOLUTION: f32 = 4096.0;
 const STEPS_PER_DEG: f32 = STEPS_PER_REVOLUTION / 360.0;
 
 /// Maximum raw velocity command (safety limit)
-const MAX_RAW: i16 = 3000;
+pub const MAX_RAW: i16 = 3000;
+
+/// Geometric parameters of the base, overridable from the runtime config
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KinematicParams {
+    pub wheel_radius: f32, // meters
+    pub base_radius: f32,  // meters
+    pub max_raw: i16,      // raw velocity safety limit
+}
+
+impl Default for KinematicParams {
+    fn default() -> Self {
+        Self {
+            wheel_radius: WHEEL_RADIUS,
+            base_radius: BASE_RADIUS,
+            max_raw: MAX_RAW,
+        }
+    }
+}
 
 /// Raw wheel velocity commands for the three motors
 #[derive(Debug, Clone, Copy, Default)]
@@ -41,6 +63,14 @@ impl WheelVelocities {
     }
 }
 
+/// Body-frame velocity of the base
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BodyVelocity {
+    pub x: f32,     // m/s (positive = forward)
+    pub y: f32,     // m/s (positive = left)
+    pub theta: f32, // deg/s (positive = counter-clockwise)
+}
+
 /// Convert degrees per second to raw motor ticks
 fn degps_to_raw(degps: f32) -> i16 {
     let speed_in_steps = degps * STEPS_PER_DEG;
@@ -121,6 +151,82 @@ pub fn body_to_wheel_raw_with_params(
     }
 }
 
+/// Convert raw wheel velocities back to body-frame velocities
+///
+/// This is the inverse of `body_to_wheel_raw` (without its saturation),
+/// used to turn measured wheel speeds into odometry.
+pub fn wheel_raw_to_body(wheels: WheelVelocities) -> BodyVelocity {
+    wheel_raw_to_body_with_params(wheels, WHEEL_RADIUS, BASE_RADIUS)
+}
+
+/// Convert raw wheel velocities to body-frame velocities with custom parameters
+pub fn wheel_raw_to_body_with_params(
+    wheels: WheelVelocities,
+    wheel_radius: f32,
+    base_radius: f32,
+) -> BodyVelocity {
+    wheel_steps_to_body_with_params(
+        wheels.as_array().map(|raw| raw as f32),
+        wheel_radius,
+        base_radius,
+    )
+}
+
+/// Convert continuous wheel velocities (steps/s, `[left, back, right]`) to
+/// body-frame velocities, e.g. the exact velocities of simulated wheels
+pub fn wheel_steps_to_body_with_params(
+    wheels: [f32; 3],
+    wheel_radius: f32,
+    base_radius: f32,
+) -> BodyVelocity {
+    // Ticks/s -> deg/s -> rad/s -> wheel linear speed (m/s)
+    let wheel_linear_speeds: [f32; 3] =
+        wheels.map(|steps| (steps / STEPS_PER_DEG) * (PI / 180.0) * wheel_radius);
+
+    // Same kinematic matrix as body_to_wheel_raw_with_params, inverted
+    let m: [[f32; 3]; 3] = WHEEL_ANGLES_DEG.map(|angle_deg| {
+        let angle_rad = angle_deg * (PI / 180.0);
+        [angle_rad.cos(), angle_rad.sin(), base_radius]
+    });
+    let inv = invert_3x3(&m);
+
+    let mut velocity = [0.0f32; 3];
+    for (row, v) in inv.iter().zip(velocity.iter_mut()) {
+        *v = row
+            .iter()
+            .zip(wheel_linear_speeds.iter())
+            .map(|(a, b)| a * b)
+            .sum();
+    }
+
+    BodyVelocity {
+        x: velocity[0],
+        y: velocity[1],
+        theta: velocity[2] * (180.0 / PI),
+    }
+}
+
+/// Invert a 3x3 matrix via its adjugate
+///
+/// The wheel matrix is always invertible for three wheels 120° apart.
+fn invert_3x3(m: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+
+    let mut inv = [[0.0f32; 3]; 3];
+    for (i, row) in inv.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            // Cofactor of m[j][i] (transposed for the adjugate)
+            let r = [(j + 1) % 3, (j + 2) % 3];
+            let c = [(i + 1) % 3, (i + 2) % 3];
+            let cofactor = m[r[0]][c[0]] * m[r[1]][c[1]] - m[r[0]][c[1]] * m[r[1]][c[0]];
+            *value = cofactor / det;
+        }
+    }
+    inv
+}
+
 //
 ///
 /// These tests are used to verify the correctness of the kinematics module.
@@ -247,4 +353,31 @@ mod tests {
         assert!(wheels.back.abs() <= 3000, "Back wheel not clamped");
         assert!(wheels.right.abs() <= 3000, "Right wheel not clamped");
     }
+
+    #[test]
+    fn test_forward_kinematics_zero() {
+        let body = wheel_raw_to_body(WheelVelocities::zero());
+        assert_eq!(body, BodyVelocity::default());
+    }
+
+    #[test]
+    fn test_forward_inverts_inverse() {
+        // Round trip through raw ticks loses at most rounding precision
+        for (x, y, theta) in [
+            (0.1, 0.0, 0.0),
+            (0.0, -0.2, 0.0),
+            (0.0, 0.0, 45.0),
+            (0.1, 0.05, -30.0),
+        ] {
+            let body = wheel_raw_to_body(body_to_wheel_raw(x, y, theta));
+            assert!((body.x - x).abs() < 1e-3, "x: {} vs {}", body.x, x);
+            assert!((body.y - y).abs() < 1e-3, "y: {} vs {}", body.y, y);
+            assert!(
+                (body.theta - theta).abs() < 0.1,
+                "theta: {} vs {}",
+                body.theta,
+                theta
+            );
+        }
+    }
 }
@@ -2,11 +2,29 @@
 //
 // Provides:
 // - Omniwheel inverse kinematics (body velocity -> wheel velocities)
-// - Feetech STS3215 serial protocol implementation
-// - High-level motor driver API
+// - Feetech STS3215 serial protocol implementation and register map
+// - Streaming status packet decoder that resynchronises on corrupted input
+// - High-level motor driver API, with position control in degrees or radians
+// - Position-mode driver for the SO-100 arm on the same bus
+// - Per-motor calibration (homing offset, range, direction)
+// - Safe EEPROM changes of servo ID and baud rate
+// - Transport abstraction and an in-memory servo simulator
 
+pub mod angle;
+mod arm;
+pub mod calibration;
+pub mod decoder;
 mod driver;
+pub mod eeprom;
 pub mod feetech;
 pub mod kinematics;
+pub mod registers;
+pub mod sim;
+pub mod transport;
 
-pub use driver::{MotorDriver, BASE_MOTOR_IDS, MOTOR_ID_BACK, MOTOR_ID_LEFT, MOTOR_ID_RIGHT};
+pub use angle::{Angle, MoveProfile};
+pub use arm::{ARM_MOTOR_IDS, ArmDriver, JOINT_NAMES, JointFeedback};
+pub use calibration::{Calibration, Calibrator, Direction, MotorCalibration};
+pub use driver::{
+    BASE_MOTOR_IDS, MOTOR_ID_BACK, MOTOR_ID_LEFT, MOTOR_ID_RIGHT, MotorDriver, MotorFeedback,
+};
@@ -0,0 +1,346 @@
+// STS3215 register map
+//
+// Every register carries its address, width, value encoding, memory area and
+// access flag, so callers can read and write typed values through
+// `FeetechBus::read` / `FeetechBus::write` instead of hand-coding byte layouts.
+
+/// How a register value is stored on the wire (little-endian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Plain unsigned integer
+    Unsigned,
+    /// Sign-magnitude: `sign_bit` set = negative, lower bits = magnitude
+    SignMagnitude { sign_bit: u8 },
+}
+
+/// Where a register lives in the servo's memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryArea {
+    /// Persists across power cycles; writes require `Register::Lock` = 0
+    Eeprom,
+    /// Volatile, reset on power up
+    Ram,
+}
+
+/// Static description of a register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterInfo {
+    pub address: u8,
+    pub width: u8, // bytes
+    pub encoding: Encoding,
+    pub area: MemoryArea,
+    pub read_only: bool,
+}
+
+/// Register addresses for STS3215
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    // EEPROM area (persists across power cycles)
+    FirmwareMajor = 0,              // 1 byte, read-only
+    FirmwareMinor = 1,              // 1 byte, read-only
+    ModelNumber = 3,                // 2 bytes, read-only
+    Id = 5,                         // 1 byte
+    BaudRate = 6,                   // 1 byte: index into the baud rate table
+    ReturnDelayTime = 7,            // 1 byte (2 µs units)
+    ResponseStatusLevel = 8,        // 1 byte: 0=reply to ping/read only, 1=reply to all
+    MinPositionLimit = 9,           // 2 bytes (steps)
+    MaxPositionLimit = 11,          // 2 bytes (steps)
+    MaxTemperatureLimit = 13,       // 1 byte (°C)
+    MaxVoltageLimit = 14,           // 1 byte (0.1 V)
+    MinVoltageLimit = 15,           // 1 byte (0.1 V)
+    MaxTorqueLimit = 16,            // 2 bytes (0.1%)
+    Phase = 18,                     // 1 byte
+    UnloadingCondition = 19,        // 1 byte: error bits that disable torque
+    LedAlarmCondition = 20,         // 1 byte: error bits that flash the LED
+    PCoefficient = 21,              // 1 byte: position loop P gain
+    DCoefficient = 22,              // 1 byte: position loop D gain
+    ICoefficient = 23,              // 1 byte: position loop I gain
+    MinimumStartupForce = 24,       // 2 bytes (0.1%)
+    CwDeadZone = 26,                // 1 byte (steps)
+    CcwDeadZone = 27,               // 1 byte (steps)
+    ProtectionCurrent = 28,         // 2 bytes (6.5 mA)
+    AngularResolution = 30,         // 1 byte
+    HomingOffset = 31,              // 2 bytes (signed, bit 11 = direction)
+    OperatingMode = 33,             // 1 byte: 0=position, 1=velocity, 2=PWM, 3=step
+    ProtectiveTorque = 34,          // 1 byte (%)
+    ProtectionTime = 35,            // 1 byte (10 ms)
+    OverloadTorque = 36,            // 1 byte (%)
+    VelocityPCoefficient = 37,      // 1 byte: velocity loop P gain
+    OverCurrentProtectionTime = 38, // 1 byte (10 ms)
+    VelocityICoefficient = 39,      // 1 byte: velocity loop I gain
+
+    // RAM area (volatile)
+    TorqueEnable = 40,        // 1 byte: 0=off, 1=on
+    Acceleration = 41,        // 1 byte (100 steps/s²)
+    GoalPosition = 42,        // 2 bytes (signed, bit 15 = direction)
+    GoalTime = 44,            // 2 bytes (ms)
+    GoalVelocity = 46,        // 2 bytes (signed, velocity mode)
+    TorqueLimit = 48,         // 2 bytes (0.1%)
+    Lock = 55,                // 1 byte: 0=unlocked, 1=locked
+    PresentPosition = 56,     // 2 bytes, read-only (signed, bit 15 = direction)
+    PresentVelocity = 58,     // 2 bytes, read-only (signed)
+    PresentLoad = 60,         // 2 bytes, read-only (signed, bit 10 = direction)
+    PresentVoltage = 62,      // 1 byte, read-only (0.1 V)
+    PresentTemperature = 63,  // 1 byte, read-only (°C)
+    Status = 65,              // 1 byte, read-only: hardware error bits
+    Moving = 66,              // 1 byte, read-only: 1 while moving
+    PresentCurrent = 69,      // 2 bytes, read-only (6.5 mA)
+    MaximumAcceleration = 85, // 2 bytes
+}
+
+impl Register {
+    /// Every register, in address order
+    pub const ALL: [Register; 48] = [
+        Register::FirmwareMajor,
+        Register::FirmwareMinor,
+        Register::ModelNumber,
+        Register::Id,
+        Register::BaudRate,
+        Register::ReturnDelayTime,
+        Register::ResponseStatusLevel,
+        Register::MinPositionLimit,
+        Register::MaxPositionLimit,
+        Register::MaxTemperatureLimit,
+        Register::MaxVoltageLimit,
+        Register::MinVoltageLimit,
+        Register::MaxTorqueLimit,
+        Register::Phase,
+        Register::UnloadingCondition,
+        Register::LedAlarmCondition,
+        Register::PCoefficient,
+        Register::DCoefficient,
+        Register::ICoefficient,
+        Register::MinimumStartupForce,
+        Register::CwDeadZone,
+        Register::CcwDeadZone,
+        Register::ProtectionCurrent,
+        Register::AngularResolution,
+        Register::HomingOffset,
+        Register::OperatingMode,
+        Register::ProtectiveTorque,
+        Register::ProtectionTime,
+        Register::OverloadTorque,
+        Register::VelocityPCoefficient,
+        Register::OverCurrentProtectionTime,
+        Register::VelocityICoefficient,
+        Register::TorqueEnable,
+        Register::Acceleration,
+        Register::GoalPosition,
+        Register::GoalTime,
+        Register::GoalVelocity,
+        Register::TorqueLimit,
+        Register::Lock,
+        Register::PresentPosition,
+        Register::PresentVelocity,
+        Register::PresentLoad,
+        Register::PresentVoltage,
+        Register::PresentTemperature,
+        Register::Status,
+        Register::Moving,
+        Register::PresentCurrent,
+        Register::MaximumAcceleration,
+    ];
+
+    /// Full description of this register
+    pub fn info(self) -> RegisterInfo {
+        use Register::*;
+
+        let width = match self {
+            ModelNumber | MinPositionLimit | MaxPositionLimit | MaxTorqueLimit
+            | MinimumStartupForce | ProtectionCurrent | HomingOffset | GoalPosition | GoalTime
+            | GoalVelocity | TorqueLimit | PresentPosition | PresentVelocity | PresentLoad
+            | PresentCurrent | MaximumAcceleration => 2,
+            _ => 1,
+        };
+        let encoding = match self {
+            HomingOffset => Encoding::SignMagnitude { sign_bit: 11 },
+            GoalPosition | GoalVelocity | PresentPosition | PresentVelocity => {
+                Encoding::SignMagnitude { sign_bit: 15 }
+            }
+            PresentLoad => Encoding::SignMagnitude { sign_bit: 10 },
+            _ => Encoding::Unsigned,
+        };
+        let read_only = matches!(
+            self,
+            FirmwareMajor
+                | FirmwareMinor
+                | ModelNumber
+                | PresentPosition
+                | PresentVelocity
+                | PresentLoad
+                | PresentVoltage
+                | PresentTemperature
+                | Status
+                | Moving
+                | PresentCurrent
+        );
+        let area = if (self as u8) < TorqueEnable as u8 {
+            MemoryArea::Eeprom
+        } else {
+            MemoryArea::Ram
+        };
+
+        RegisterInfo {
+            address: self as u8,
+            width,
+            encoding,
+            area,
+            read_only,
+        }
+    }
+
+    pub fn address(self) -> u8 {
+        self as u8
+    }
+
+    pub fn width(self) -> u8 {
+        self.info().width
+    }
+
+    pub fn is_read_only(self) -> bool {
+        self.info().read_only
+    }
+
+    /// Look up the register starting at `address`
+    pub fn from_address(address: u8) -> Option<Register> {
+        Self::ALL.iter().copied().find(|r| r.address() == address)
+    }
+
+    /// Largest magnitude representable in this register
+    fn max_magnitude(self) -> i32 {
+        let info = self.info();
+        match info.encoding {
+            Encoding::Unsigned => (1i32 << (8 * info.width as i32)) - 1,
+            Encoding::SignMagnitude { sign_bit } => (1i32 << sign_bit) - 1,
+        }
+    }
+
+    /// Check that `value` fits this register's width and encoding
+    pub fn check_range(self, value: i32) -> bool {
+        match self.info().encoding {
+            Encoding::Unsigned => (0..=self.max_magnitude()).contains(&value),
+            Encoding::SignMagnitude { .. } => value.unsigned_abs() <= self.max_magnitude() as u32,
+        }
+    }
+
+    /// Encode a value into its little-endian wire bytes
+    ///
+    /// The value must satisfy `check_range`; out-of-range bits are masked off.
+    pub fn encode(self, value: i32) -> Vec<u8> {
+        let info = self.info();
+        let raw = match info.encoding {
+            Encoding::Unsigned => value as u32,
+            Encoding::SignMagnitude { sign_bit } => {
+                let magnitude = value.unsigned_abs() & ((1 << sign_bit) - 1);
+                if value < 0 {
+                    magnitude | (1 << sign_bit)
+                } else {
+                    magnitude
+                }
+            }
+        };
+        raw.to_le_bytes()[..info.width as usize].to_vec()
+    }
+
+    /// Decode little-endian wire bytes into a value
+    ///
+    /// `bytes` must hold at least `width()` bytes.
+    pub fn decode(self, bytes: &[u8]) -> i32 {
+        let info = self.info();
+        let raw = bytes[..info.width as usize]
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        match info.encoding {
+            Encoding::Unsigned => raw as i32,
+            Encoding::SignMagnitude { sign_bit } => {
+                let magnitude = (raw & ((1 << sign_bit) - 1)) as i32;
+                if raw & (1 << sign_bit) != 0 {
+                    -magnitude
+                } else {
+                    magnitude
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_consistent() {
+        // Addresses strictly increase and registers never overlap
+        for pair in Register::ALL.windows(2) {
+            let (a, b) = (pair[0].info(), pair[1].info());
+            assert!(
+                a.address + a.width <= b.address,
+                "{:?} overlaps {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+        for r in Register::ALL {
+            assert_eq!(Register::from_address(r.address()), Some(r));
+        }
+    }
+
+    #[test]
+    fn test_memory_areas() {
+        assert_eq!(Register::Id.info().area, MemoryArea::Eeprom);
+        assert_eq!(Register::OperatingMode.info().area, MemoryArea::Eeprom);
+        assert_eq!(Register::TorqueEnable.info().area, MemoryArea::Ram);
+        assert!(Register::PresentLoad.is_read_only());
+        assert!(!Register::GoalVelocity.is_read_only());
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        for (register, value) in [
+            (Register::GoalVelocity, -100),
+            (Register::GoalVelocity, 3000),
+            (Register::HomingOffset, -2047),
+            (Register::PresentLoad, -500),
+            (Register::ModelNumber, 777),
+            (Register::Id, 253),
+        ] {
+            let bytes = register.encode(value);
+            assert_eq!(bytes.len(), register.width() as usize);
+            assert_eq!(register.decode(&bytes), value, "{:?}", register);
+        }
+        assert_eq!(Register::GoalVelocity.encode(-100), vec![0x64, 0x80]);
+        assert_eq!(Register::HomingOffset.encode(-1), vec![0x01, 0x08]);
+    }
+
+    #[test]
+    fn test_check_range() {
+        assert!(Register::Id.check_range(253));
+        assert!(!Register::Id.check_range(256));
+        assert!(!Register::Id.check_range(-1));
+        assert!(Register::HomingOffset.check_range(-2047));
+        assert!(!Register::HomingOffset.check_range(2048));
+        assert!(Register::GoalVelocity.check_range(-32767));
+        assert!(!Register::GoalVelocity.check_range(i32::MIN));
+        assert!(!Register::Id.check_range(i32::MIN));
+    }
+
+    #[test]
+    fn test_sign_magnitude_registers() {
+        let word = |v: i32| {
+            let b = Register::GoalVelocity.encode(v);
+            u16::from_le_bytes([b[0], b[1]])
+        };
+        assert_eq!(word(0), 0);
+        assert_eq!(word(100), 100);
+        assert_eq!(word(-100), 0x8064); // 0x8000 | 100
+        assert_eq!(word(-1), 0x8001);
+
+        let velocity = |raw: u16| Register::PresentVelocity.decode(&raw.to_le_bytes());
+        assert_eq!(velocity(0), 0);
+        assert_eq!(velocity(100), 100);
+        assert_eq!(velocity(0x8064), -100);
+        assert_eq!(velocity(0x8001), -1);
+    }
+}
@@ -0,0 +1,762 @@
+// In-memory simulation of a chain of Feetech STS3215 servos
+//
+// The simulator sits below `FeetechBus` as a `Transport`: it parses the
+// instruction packets written to it, applies them to a per-servo register
+// file and queues the status packets a real servo would send back. Velocity
+// and position are integrated against wall-clock time whenever the bus is
+// accessed, so the runtime and examples behave like they do on hardware.
+// Tests and the simulated base can switch to a manual clock instead, which
+// only moves when advanced explicitly.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::feetech::{
+    BROADCAST_ID, DEFAULT_BAUDRATE, FeetechBus, HEADER, Instruction, OperatingMode, baud_rate_index,
+};
+use super::registers::{MemoryArea, Register};
+use super::transport::Transport;
+
+/// Size of the simulated register file (covers the whole STS3215 map)
+const REGISTER_COUNT: usize = 128;
+
+/// STS3215 model number as reported in `Register::ModelNumber`
+pub const STS3215_MODEL: u16 = 777;
+
+/// Speed used for position moves when no goal speed is set (steps/s)
+const DEFAULT_POSITION_SPEED: f64 = 3400.0;
+
+/// Steps per revolution of the magnetic encoder
+const STEPS_PER_REVOLUTION: f64 = 4096.0;
+
+/// Longest integration step (s); longer intervals are split
+const MAX_STEP: f64 = 0.001;
+
+/// How simulated servos respond to velocity goals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimDynamics {
+    /// Time constant of the first-order velocity response (s), 0 = instant
+    pub lag: f64,
+    /// Velocity the motor saturates at (steps/s)
+    pub max_velocity: f64,
+    /// Standard deviation of the noise on present velocity (steps/s)
+    pub noise: f64,
+}
+
+impl Default for SimDynamics {
+    /// Ideal motors: goals are reached instantly and measured exactly
+    fn default() -> Self {
+        Self {
+            lag: 0.0,
+            max_velocity: 32767.0,
+            noise: 0.0,
+        }
+    }
+}
+
+/// Small deterministic PRNG (xorshift64) for measurement noise
+#[derive(Debug, Clone)]
+struct NoiseSource(u64);
+
+impl NoiseSource {
+    fn uniform(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal sample (Box-Muller)
+    fn gaussian(&mut self) -> f64 {
+        let u = 1.0 - self.uniform(); // (0, 1], keeps ln finite
+        let v = self.uniform();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
+
+/// One simulated servo: a register file plus continuous motion state
+#[derive(Debug, Clone)]
+struct SimServo {
+    registers: [u8; REGISTER_COUNT],
+    position: f64,                    // steps, kept unwrapped for sub-step precision
+    velocity: f64,                    // true velocity (steps/s), before measurement noise
+    staged: Option<(usize, Vec<u8>)>, // RegWrite waiting for Action: address, data
+}
+
+impl SimServo {
+    fn new(id: u8) -> Self {
+        let mut servo = Self {
+            registers: [0; REGISTER_COUNT],
+            position: 2048.0,
+            velocity: 0.0,
+            staged: None,
+        };
+        for (register, value) in [
+            (Register::FirmwareMajor, 3),
+            (Register::FirmwareMinor, 10),
+            (Register::ModelNumber, STS3215_MODEL as i32),
+            (Register::Id, id as i32),
+            (Register::BaudRate, 0), // 1 Mbps
+            (Register::MinPositionLimit, 0),
+            (Register::MaxPositionLimit, 4095),
+            (Register::MaxTemperatureLimit, 70), // °C
+            (Register::MaxVoltageLimit, 140),    // 0.1 V
+            (Register::MinVoltageLimit, 40),     // 0.1 V
+            (Register::PresentVoltage, 120),     // 0.1 V
+            (Register::PresentTemperature, 30),  // °C
+        ] {
+            servo.set(register, value);
+        }
+        servo.sync_present();
+        servo
+    }
+
+    fn id(&self) -> u8 {
+        self.get(Register::Id) as u8
+    }
+
+    /// Whether this servo understands a host talking at `baud_rate`
+    fn listens_at(&self, baud_rate: u32) -> bool {
+        baud_rate_index(baud_rate).map(i32::from) == Some(self.get(Register::BaudRate))
+    }
+
+    /// Typed register value, bypassing access checks
+    fn get(&self, register: Register) -> i32 {
+        register.decode(&self.registers[register.address() as usize..])
+    }
+
+    /// Set a typed register value, bypassing access checks
+    fn set(&mut self, register: Register, value: i32) {
+        let addr = register.address() as usize;
+        let bytes = register.encode(value);
+        self.registers[addr..addr + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    fn torque_enabled(&self) -> bool {
+        self.get(Register::TorqueEnable) == 1
+    }
+
+    /// Register covering a byte address, if any
+    fn register_at(addr: usize) -> Option<Register> {
+        Register::ALL.into_iter().find(|r| {
+            let start = r.address() as usize;
+            (start..start + r.width() as usize).contains(&addr)
+        })
+    }
+
+    /// Apply a host write, honouring read-only registers and the EEPROM lock
+    fn write(&mut self, addr: usize, data: &[u8]) {
+        let locked = self.get(Register::Lock) != 0;
+        for (offset, &byte) in data.iter().enumerate() {
+            let a = addr + offset;
+            if a >= REGISTER_COUNT {
+                continue;
+            }
+            if let Some(info) = Self::register_at(a).map(Register::info)
+                && (info.read_only || (locked && info.area == MemoryArea::Eeprom))
+            {
+                continue;
+            }
+            self.registers[a] = byte;
+        }
+    }
+
+    fn read(&self, addr: usize, len: usize) -> Vec<u8> {
+        (addr..addr + len)
+            .map(|a| self.registers.get(a).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// Advance the servo state by `dt` seconds
+    fn step(&mut self, dt: f64, dynamics: &SimDynamics, noise: &mut NoiseSource) {
+        let mode = self.get(Register::OperatingMode);
+        let (velocity, moving) = if !self.torque_enabled() {
+            (0.0, false)
+        } else if mode == OperatingMode::Velocity as i32 {
+            let goal = (self.get(Register::GoalVelocity) as f64)
+                .clamp(-dynamics.max_velocity, dynamics.max_velocity);
+            let velocity = if dynamics.lag > 0.0 {
+                self.velocity + (goal - self.velocity) * (1.0 - (-dt / dynamics.lag).exp())
+            } else {
+                goal
+            };
+            (velocity, goal != 0.0)
+        } else if mode == OperatingMode::Position as i32 {
+            let goal = self.get(Register::GoalPosition) as f64;
+            let error = goal - self.position.rem_euclid(STEPS_PER_REVOLUTION);
+            let speed = match self.get(Register::GoalVelocity).abs() {
+                0 => DEFAULT_POSITION_SPEED,
+                s => s as f64,
+            };
+            let velocity = if dt > 0.0 {
+                (error / dt).clamp(-speed, speed)
+            } else {
+                0.0
+            };
+            // Still moving if the goal is not reached by the end of this step
+            (velocity, (error - velocity * dt).abs() >= 1.0)
+        } else {
+            (0.0, false)
+        };
+
+        self.position += velocity * dt;
+        self.velocity = velocity;
+        let measured = if dynamics.noise > 0.0 && velocity != 0.0 {
+            velocity + dynamics.noise * noise.gaussian()
+        } else {
+            velocity
+        };
+        let velocity_raw = measured.round().clamp(-32767.0, 32767.0) as i32;
+        self.set(Register::PresentVelocity, velocity_raw);
+        self.set(Register::Moving, moving as i32);
+        self.sync_present();
+    }
+
+    fn sync_present(&mut self) {
+        let position = self.position.rem_euclid(STEPS_PER_REVOLUTION) as i32;
+        self.set(Register::PresentPosition, position);
+    }
+}
+
+/// Shared state of all servos on the simulated chain
+#[derive(Debug)]
+struct BankState {
+    servos: Vec<SimServo>,
+    last_update: Instant,
+    clock: Option<Instant>, // manual clock: simulated now, None = wall clock
+    connected: bool,        // false = USB adapter unplugged
+    echo: bool,             // adapter hands transmitted bytes back, like some half-duplex boards
+    dynamics: SimDynamics,
+    noise: NoiseSource,
+}
+
+impl BankState {
+    /// Bring the servos up to the current time, in steps of at most `MAX_STEP`
+    fn advance(&mut self) {
+        let now = self.clock.unwrap_or_else(Instant::now);
+        let dt = now
+            .saturating_duration_since(self.last_update)
+            .as_secs_f64();
+        self.last_update = now;
+        let steps = (dt / MAX_STEP).ceil() as usize;
+        for _ in 0..steps {
+            for servo in &mut self.servos {
+                servo.step(dt / steps as f64, &self.dynamics, &mut self.noise);
+            }
+        }
+    }
+
+    fn servo_mut(&mut self, id: u8) -> Option<&mut SimServo> {
+        self.servos.iter_mut().find(|s| s.id() == id)
+    }
+
+    /// Servo with this ID, if it listens at `baud_rate`
+    fn listener_mut(&mut self, id: u8, baud_rate: u32) -> Option<&mut SimServo> {
+        self.servos
+            .iter_mut()
+            .find(|s| s.id() == id && s.listens_at(baud_rate))
+    }
+
+    /// Execute one instruction packet, returning the status packets to send back
+    ///
+    /// Servos set to another baud rate than the host's do not see the packet.
+    fn execute(&mut self, id: u8, instruction: u8, params: &[u8], baud_rate: u32) -> Vec<Vec<u8>> {
+        self.advance();
+
+        let mut responses = Vec::new();
+        match instruction {
+            i if i == Instruction::Ping as u8 => {
+                for servo in self.servos.iter().filter(|s| s.listens_at(baud_rate)) {
+                    if id == BROADCAST_ID || servo.id() == id {
+                        responses.push(status_packet(servo.id(), 0, &[]));
+                    }
+                }
+            }
+            i if i == Instruction::Read as u8 && params.len() == 2 => {
+                if let Some(servo) = self.listener_mut(id, baud_rate) {
+                    let data = servo.read(params[0] as usize, params[1] as usize);
+                    responses.push(status_packet(id, 0, &data));
+                }
+            }
+            i if i == Instruction::Write as u8 && !params.is_empty() => {
+                for servo in self.servos.iter_mut().filter(|s| s.listens_at(baud_rate)) {
+                    // Reply with the ID it had when the packet arrived
+                    let servo_id = servo.id();
+                    if id == BROADCAST_ID || servo_id == id {
+                        servo.write(params[0] as usize, &params[1..]);
+                        if id != BROADCAST_ID {
+                            responses.push(status_packet(servo_id, 0, &[]));
+                        }
+                    }
+                }
+            }
+            // Like Write, but held until the next Action; a new RegWrite replaces it
+            i if i == Instruction::RegWrite as u8 && !params.is_empty() => {
+                for servo in self.servos.iter_mut().filter(|s| s.listens_at(baud_rate)) {
+                    if id == BROADCAST_ID || servo.id() == id {
+                        servo.staged = Some((params[0] as usize, params[1..].to_vec()));
+                        if id != BROADCAST_ID {
+                            responses.push(status_packet(id, 0, &[]));
+                        }
+                    }
+                }
+            }
+            i if i == Instruction::Action as u8 => {
+                for servo in self.servos.iter_mut().filter(|s| s.listens_at(baud_rate)) {
+                    let servo_id = servo.id();
+                    if (id == BROADCAST_ID || servo_id == id)
+                        && let Some((addr, data)) = servo.staged.take()
+                    {
+                        servo.write(addr, &data);
+                        if id != BROADCAST_ID {
+                            responses.push(status_packet(servo_id, 0, &[]));
+                        }
+                    }
+                }
+            }
+            i if i == Instruction::SyncRead as u8 && params.len() >= 2 => {
+                let (addr, len) = (params[0] as usize, params[1] as usize);
+                for &servo_id in &params[2..] {
+                    if let Some(servo) = self.listener_mut(servo_id, baud_rate) {
+                        let data = servo.read(addr, len);
+                        responses.push(status_packet(servo_id, 0, &data));
+                    }
+                }
+            }
+            i if i == Instruction::SyncWrite as u8 && params.len() >= 2 => {
+                let addr = params[0] as usize;
+                let len = params[1] as usize;
+                for chunk in params[2..].chunks_exact(len + 1) {
+                    if let Some(servo) = self.listener_mut(chunk[0], baud_rate) {
+                        servo.write(addr, &chunk[1..]);
+                    }
+                }
+            }
+            _ => {} // unsupported instructions get no reply, like a real servo
+        }
+
+        // Apply new goals immediately so the next read reflects them
+        for servo in &mut self.servos {
+            servo.step(0.0, &self.dynamics, &mut self.noise);
+        }
+        responses
+    }
+}
+
+/// Build a status packet: [0xFF, 0xFF, ID, Length, Error, Params..., Checksum]
+fn status_packet(id: u8, error: u8, params: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(6 + params.len());
+    packet.extend_from_slice(&HEADER);
+    packet.push(id);
+    packet.push((params.len() + 2) as u8);
+    packet.push(error);
+    packet.extend_from_slice(params);
+    packet.push(FeetechBus::checksum(&packet[2..]));
+    packet
+}
+
+/// A simulated chain of STS3215 servos
+///
+/// Cloning gives another handle to the same servos, so a test or simulator
+/// can inspect state while a `FeetechBus` drives it through `transport()`.
+#[derive(Debug, Clone)]
+pub struct SimServoBank {
+    state: Arc<Mutex<BankState>>,
+}
+
+impl SimServoBank {
+    /// Create servos with the given IDs, in position mode with torque off
+    pub fn new(ids: &[u8]) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BankState {
+                servos: ids.iter().map(|&id| SimServo::new(id)).collect(),
+                last_update: Instant::now(),
+                clock: None,
+                connected: true,
+                echo: false,
+                dynamics: SimDynamics::default(),
+                noise: NoiseSource(0x2545_F491_4F6C_DD1D),
+            })),
+        }
+    }
+
+    /// Full LeKiwi chain: arm servos 1-6 and base wheels 7-9
+    pub fn lekiwi() -> Self {
+        Self::new(&[1, 2, 3, 4, 5, 6, 7, 8, 9])
+    }
+
+    /// A transport for `FeetechBus::new` talking to these servos
+    pub fn transport(&self) -> SimTransport {
+        SimTransport {
+            bank: self.clone(),
+            baud_rate: DEFAULT_BAUDRATE,
+            incoming: Vec::new(),
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BankState> {
+        // A panic while holding the lock cannot leave the register file inconsistent
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Simulate unplugging (`false`) or replugging (`true`) the bus adapter
+    ///
+    /// While unplugged every transport operation fails with an I/O error,
+    /// but the servos keep executing their last goals.
+    pub fn set_connected(&self, connected: bool) {
+        self.lock().connected = connected;
+    }
+
+    /// Make the adapter echo every transmitted byte ahead of the replies
+    pub fn set_echo(&self, echo: bool) {
+        self.lock().echo = echo;
+    }
+
+    /// Move simulated time forward by `dt`
+    ///
+    /// From the first call on the servos no longer follow wall-clock time:
+    /// they only move through this method, which makes them deterministic.
+    pub fn advance_clock(&self, dt: Duration) {
+        let mut state = self.lock();
+        if state.clock.is_none() {
+            state.advance();
+            state.clock = Some(state.last_update);
+        }
+        state.clock = state.clock.map(|now| now + dt);
+        state.advance();
+    }
+
+    /// Change how the servos respond to velocity goals
+    pub fn set_dynamics(&self, dynamics: SimDynamics) {
+        let mut state = self.lock();
+        state.advance();
+        state.dynamics = dynamics;
+    }
+
+    /// IDs of all simulated servos
+    pub fn ids(&self) -> Vec<u8> {
+        self.lock().servos.iter().map(|s| s.id()).collect()
+    }
+
+    /// Read a typed register value from a servo, bypassing the protocol
+    pub fn peek(&self, id: u8, register: Register) -> Option<i32> {
+        let mut state = self.lock();
+        state.advance();
+        state.servo_mut(id).map(|servo| servo.get(register))
+    }
+
+    /// Current velocity of a servo in raw steps/s
+    pub fn present_velocity(&self, id: u8) -> Option<i16> {
+        self.peek(id, Register::PresentVelocity).map(|v| v as i16)
+    }
+
+    /// True velocity of a servo in steps/s, without measurement noise
+    pub fn true_velocity(&self, id: u8) -> Option<f64> {
+        let mut state = self.lock();
+        state.advance();
+        state.servo_mut(id).map(|servo| servo.velocity)
+    }
+
+    /// Current position of a servo in raw steps (0-4095)
+    pub fn present_position(&self, id: u8) -> Option<u16> {
+        self.peek(id, Register::PresentPosition).map(|p| p as u16)
+    }
+
+    /// Turn a servo to a raw position from the outside, like moving it by hand
+    pub fn set_position(&self, id: u8, steps: u16) {
+        let mut state = self.lock();
+        state.advance();
+        if let Some(servo) = state.servo_mut(id) {
+            servo.position = steps as f64;
+            servo.sync_present();
+        }
+    }
+}
+
+/// `Transport` end of a `SimServoBank`
+#[derive(Debug)]
+pub struct SimTransport {
+    bank: SimServoBank,
+    baud_rate: u32,
+    incoming: Vec<u8>,      // bytes written by the host, not yet parsed
+    outgoing: VecDeque<u8>, // status bytes waiting to be read by the host
+}
+
+impl SimTransport {
+    fn check_connected(&self) -> io::Result<()> {
+        if self.bank.lock().connected {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "simulated bus adapter unplugged",
+            ))
+        }
+    }
+
+    /// Parse and execute every complete instruction packet in `incoming`
+    fn process(&mut self) {
+        loop {
+            // Resynchronise on the header, dropping anything before it
+            match self.incoming.windows(2).position(|w| w == HEADER) {
+                Some(start) => {
+                    self.incoming.drain(..start);
+                }
+                None => {
+                    let keep = usize::from(self.incoming.last() == Some(&0xFF));
+                    self.incoming.drain(..self.incoming.len() - keep);
+                    return;
+                }
+            }
+            if self.incoming.len() < 4 {
+                return;
+            }
+
+            let id = self.incoming[2];
+            let length = self.incoming[3] as usize;
+            if length < 2 {
+                self.incoming.drain(..2);
+                continue;
+            }
+            let total = length + 4;
+            if self.incoming.len() < total {
+                return;
+            }
+
+            let packet: Vec<u8> = self.incoming.drain(..total).collect();
+            if FeetechBus::checksum(&packet[2..total - 1]) != packet[total - 1] {
+                continue; // corrupted packets are ignored
+            }
+
+            let instruction = packet[4];
+            let params = &packet[5..total - 1];
+            let responses = self
+                .bank
+                .lock()
+                .execute(id, instruction, params, self.baud_rate);
+            for response in responses {
+                self.outgoing.extend(response);
+            }
+        }
+    }
+}
+
+impl Read for SimTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_connected()?;
+        if self.outgoing.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "no response from simulated servo",
+            ));
+        }
+        let n = buf.len().min(self.outgoing.len());
+        for (dst, src) in buf.iter_mut().zip(self.outgoing.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for SimTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_connected()?;
+        if self.bank.lock().echo {
+            self.outgoing.extend(buf);
+        }
+        self.incoming.extend_from_slice(buf);
+        self.process();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for SimTransport {
+    fn clear_input(&mut self) -> io::Result<()> {
+        self.check_connected()?;
+        self.outgoing.clear();
+        Ok(())
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> io::Result<()> {
+        self.check_connected()?;
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, _timeout: Duration) -> io::Result<()> {
+        Ok(()) // responses are available immediately
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::feetech::FeetechError;
+    use std::time::Duration;
+
+    fn sim_bus(ids: &[u8]) -> (SimServoBank, FeetechBus) {
+        let bank = SimServoBank::new(ids);
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        (bank, bus)
+    }
+
+    #[test]
+    fn test_ping() {
+        let (_, mut bus) = sim_bus(&[7, 8]);
+        assert!(bus.ping(7).unwrap());
+        assert!(bus.ping(8).unwrap());
+        assert!(!bus.ping(9).unwrap());
+    }
+
+    #[test]
+    fn test_read_write_registers() {
+        let (_, mut bus) = sim_bus(&[7]);
+        assert_eq!(
+            bus.read(7, Register::ModelNumber).unwrap(),
+            STS3215_MODEL as i32
+        );
+        assert_eq!(bus.read(7, Register::TorqueEnable).unwrap(), 0);
+
+        bus.enable_torque(7).unwrap();
+        assert_eq!(bus.read(7, Register::TorqueEnable).unwrap(), 1);
+        assert_eq!(bus.read(7, Register::Lock).unwrap(), 1);
+
+        bus.write(7, Register::HomingOffset, -300).unwrap();
+        assert_eq!(
+            bus.read(7, Register::HomingOffset).unwrap(),
+            0,
+            "EEPROM locked"
+        );
+        bus.write(7, Register::Lock, 0).unwrap();
+        bus.write(7, Register::HomingOffset, -300).unwrap();
+        assert_eq!(bus.read(7, Register::HomingOffset).unwrap(), -300);
+    }
+
+    #[test]
+    fn test_eeprom_lock() {
+        let (_, mut bus) = sim_bus(&[7]);
+        bus.write(7, Register::Lock, 1).unwrap();
+        bus.set_operating_mode(7, OperatingMode::Velocity).unwrap();
+        assert_eq!(bus.read(7, Register::OperatingMode).unwrap(), 0);
+
+        bus.disable_torque(7).unwrap(); // also unlocks
+        bus.set_operating_mode(7, OperatingMode::Velocity).unwrap();
+        assert_eq!(bus.read(7, Register::OperatingMode).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_sync_write_velocity() {
+        let (bank, mut bus) = sim_bus(&[7, 8, 9]);
+        for id in [7, 8, 9] {
+            bus.disable_torque(id).unwrap();
+            bus.set_operating_mode(id, OperatingMode::Velocity).unwrap();
+            bus.enable_torque(id).unwrap();
+        }
+
+        bus.sync_write_register(Register::GoalVelocity, &[(7, 500), (8, -300), (9, 0)])
+            .unwrap();
+        assert_eq!(bus.get_velocity(7).unwrap(), 500);
+        assert_eq!(bus.get_velocity(8).unwrap(), -300);
+        assert_eq!(bank.present_velocity(9), Some(0));
+
+        let start = bank.present_position(7).unwrap();
+        bank.advance_clock(Duration::from_millis(50));
+        let end = bank.present_position(7).unwrap();
+        assert_eq!(end, start + 25, "position should integrate velocity");
+
+        // The manual clock holds the servos in place
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(bank.present_position(7), Some(end));
+    }
+
+    #[test]
+    fn test_sync_read() {
+        let (_, mut bus) = sim_bus(&[7, 8, 9]);
+        let data = bus.sync_read(Register::PresentVoltage, 2, &[9, 7]).unwrap();
+        assert_eq!(data, vec![vec![120, 30], vec![120, 30]]);
+
+        let voltages = bus
+            .sync_read_register(Register::PresentVoltage, &[7, 8])
+            .unwrap();
+        assert_eq!(voltages, vec![120, 120]);
+
+        assert!(matches!(
+            bus.sync_read(Register::PresentVoltage, 2, &[7, 10]),
+            Err(FeetechError::Timeout { id: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_no_motion_without_torque() {
+        let (bank, mut bus) = sim_bus(&[7]);
+        bus.disable_torque(7).unwrap();
+        bus.set_operating_mode(7, OperatingMode::Velocity).unwrap();
+        bus.set_velocity(7, 1000).unwrap();
+        assert_eq!(bank.present_velocity(7), Some(0));
+    }
+
+    #[test]
+    fn test_dynamics_lag_saturation_and_noise() {
+        let (bank, mut bus) = sim_bus(&[7]);
+        bank.advance_clock(Duration::ZERO);
+        bank.set_dynamics(SimDynamics {
+            lag: 0.05,
+            max_velocity: 1000.0,
+            noise: 0.0,
+        });
+        bus.set_operating_mode(7, OperatingMode::Velocity).unwrap();
+        bus.enable_torque(7).unwrap();
+        bus.set_velocity(7, 3000).unwrap();
+
+        // Starts from rest, reaches 1 - 1/e of the saturation velocity after
+        // one time constant, then settles
+        assert_eq!(bank.true_velocity(7), Some(0.0));
+        bank.advance_clock(Duration::from_millis(50));
+        let rising = bank.true_velocity(7).unwrap();
+        assert!((rising - 632.1).abs() < 0.1, "{}", rising);
+        bank.advance_clock(Duration::from_millis(250));
+        let settled = bank.true_velocity(7).unwrap();
+        assert!((990.0..=1000.0).contains(&settled), "{}", settled);
+
+        bank.set_dynamics(SimDynamics {
+            noise: 20.0,
+            ..SimDynamics::default()
+        });
+        bus.set_velocity(7, 500).unwrap();
+        let samples: Vec<i16> = (0..20).map(|_| bank.present_velocity(7).unwrap()).collect();
+        assert!(
+            samples.iter().any(|&v| v != 500),
+            "measured velocity is noisy"
+        );
+        assert_eq!(bank.true_velocity(7), Some(500.0));
+    }
+
+    #[test]
+    fn test_unplugged_bus_fails_with_io_error() {
+        let (bank, mut bus) = sim_bus(&[7]);
+        bank.set_connected(false);
+        assert!(matches!(bus.ping(7), Err(FeetechError::Io(_))));
+        bank.set_connected(true);
+        assert!(bus.ping(7).unwrap());
+    }
+
+    #[test]
+    fn test_corrupted_packet_ignored() {
+        let bank = SimServoBank::new(&[7]);
+        let mut transport = bank.transport();
+        let mut packet = vec![0xFF, 0xFF, 7, 2, Instruction::Ping as u8];
+        packet.push(FeetechBus::checksum(&packet[2..]) ^ 0x01);
+        transport.write_all(&packet).unwrap();
+
+        let mut buf = [0u8; 1];
+        let err = transport.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
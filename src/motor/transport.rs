@@ -0,0 +1,37 @@
+// Byte transport underneath the Feetech protocol
+//
+// `FeetechBus` speaks the packet protocol over anything implementing
+// `Transport`: a real serial port, or the in-memory servo simulator.
+
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// Half-duplex byte stream to a chain of servos
+///
+/// Reads must fail with `io::ErrorKind::TimedOut` when no response arrives,
+/// the same way a serial port with a read timeout does.
+pub trait Transport: Read + Write + Send {
+    /// Discard any bytes received but not yet read
+    fn clear_input(&mut self) -> io::Result<()>;
+
+    /// Switch to another baud rate (bits/s)
+    fn set_baud_rate(&mut self, baud_rate: u32) -> io::Result<()>;
+
+    /// How long a read waits for a response
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+}
+
+impl Transport for Box<dyn SerialPort> {
+    fn clear_input(&mut self) -> io::Result<()> {
+        self.clear(ClearBuffer::Input).map_err(io::Error::from)
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> io::Result<()> {
+        SerialPort::set_baud_rate(self.as_mut(), baud_rate).map_err(io::Error::from)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        SerialPort::set_timeout(self.as_mut(), timeout).map_err(io::Error::from)
+    }
+}
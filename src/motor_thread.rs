@@ -0,0 +1,378 @@
+// Motor I/O thread
+//
+// Every serial transaction blocks until the servo answers or the response
+// timeout (100 ms) expires, so a missing or flaky servo can hold up the bus for
+// a long time. `MotorThread` owns the `MotorDriver` on a dedicated OS thread:
+// the control loop hands it one `Setpoint` per tick and other work through a
+// bounded queue, and collects `Report`s without ever waiting on the serial
+// port. Setpoints go to a latest-value slot rather than the queue: when the
+// thread falls behind, it skips straight to the latest one, and a stop can
+// never be dropped because the queue is full.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, warn};
+
+use crate::messages::{ArmJoints, BaseActuation};
+use crate::motor::feetech::{BusInventory, FeetechError};
+use crate::motor::kinematics::{KinematicParams, WheelVelocities};
+use crate::motor::{JointFeedback, MotorDriver, MotorFeedback};
+
+/// Requests the control loop may queue ahead of the motor thread
+pub const QUEUE_DEPTH: usize = 4;
+
+/// Opens and initializes a driver; runs on the motor thread
+pub type Opener = Box<dyn FnOnce() -> Result<MotorDriver, FeetechError> + Send>;
+
+/// Desired motor state for one tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setpoint {
+    pub base: BaseActuation,
+    pub kinematics: KinematicParams,
+    /// Latest arm goal, sent whenever it differs from the last one sent
+    pub arm: Option<ArmJoints>,
+    /// `false` releases the wheels; torque only comes back with `Request::EnableTorque`
+    pub torque: bool,
+    /// `false` releases the arm joints, likewise until `Request::EnableTorque`
+    pub arm_torque: bool,
+}
+
+/// Work for the motor thread, besides setpoints
+pub enum Request {
+    /// Replace the driver with a newly opened one
+    Connect(Opener),
+    /// Re-initialize the motors, enabling torque again
+    EnableTorque,
+    /// Sweep the bus for servos at these baud rates
+    Scan(Vec<u32>),
+    /// Answered with `Report::Flushed` once every earlier request is done
+    Flush,
+}
+
+/// Present motor state, read at the end of a cycle
+#[derive(Debug, Clone, Copy)]
+pub struct Readings {
+    pub wheels: [MotorFeedback; 3],
+    /// Raw wheel velocities last sent
+    pub commanded: WheelVelocities,
+    pub arm: Option<[JointFeedback; 6]>,
+    pub read_at: Instant,
+}
+
+/// Outcome of a request or setpoint
+#[derive(Debug)]
+pub enum Report {
+    Connected(Result<(), FeetechError>),
+    Cycle(Result<Readings, FeetechError>),
+    TorqueEnabled(Result<(), FeetechError>),
+    Scanned(Result<BusInventory, FeetechError>),
+    Flushed,
+}
+
+/// Entry in the request queue
+enum Message {
+    Request(Request),
+    /// Wake-up for a new setpoint in the slot
+    Setpoint,
+}
+
+/// Latest setpoint the motor thread has not picked up yet
+type Slot = Arc<Mutex<Option<Setpoint>>>;
+
+/// Handle to the motor thread; dropping it stops the motors and the thread
+pub struct MotorThread {
+    requests: Option<SyncSender<Message>>,
+    setpoint: Slot,
+    reports: Receiver<Report>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MotorThread {
+    pub fn spawn() -> io::Result<Self> {
+        let (request_tx, request_rx) = mpsc::sync_channel(QUEUE_DEPTH);
+        // At most one report per queue entry, so the request queue bounds this one too
+        let (report_tx, report_rx) = mpsc::channel();
+        let setpoint = Slot::default();
+        let slot = setpoint.clone();
+        let handle = thread::Builder::new()
+            .name("motor-io".to_string())
+            .spawn(move || Worker::default().run(request_rx, slot, report_tx))?;
+        Ok(Self {
+            requests: Some(request_tx),
+            setpoint,
+            reports: report_rx,
+            handle: Some(handle),
+        })
+    }
+
+    /// Queue a request without waiting; hands it back if the queue is full
+    pub fn send(&self, request: Request) -> Result<(), Request> {
+        let requests = self.requests.as_ref().expect("open until dropped");
+        match requests.try_send(Message::Request(request)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(Message::Request(r)))
+            | Err(TrySendError::Disconnected(Message::Request(r))) => Err(r),
+            Err(_) => unreachable!("sent a request"),
+        }
+    }
+
+    /// Apply a setpoint next, replacing one the thread has not picked up yet
+    ///
+    /// Never waits and never fails: the thread applies the latest setpoint
+    /// before its next queued request, so a stop cannot be lost.
+    pub fn send_setpoint(&self, setpoint: Setpoint) {
+        *self.setpoint.lock().unwrap_or_else(PoisonError::into_inner) = Some(setpoint);
+        let requests = self.requests.as_ref().expect("open until dropped");
+        // A full queue is fine: the thread takes the slot before the queued requests
+        let _ = requests.try_send(Message::Setpoint);
+    }
+
+    /// Next report, if one is ready
+    pub fn try_recv(&self) -> Option<Report> {
+        self.reports.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for the next report
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Report> {
+        self.reports.recv_timeout(timeout).ok()
+    }
+}
+
+impl Drop for MotorThread {
+    fn drop(&mut self) {
+        // Closing the queue ends the thread, which drops (and stops) the driver
+        self.requests = None;
+        if let Some(handle) = self.handle.take()
+            && handle.join().is_err()
+        {
+            error!("Motor thread panicked");
+        }
+    }
+}
+
+/// State owned by the motor thread
+#[derive(Default)]
+struct Worker {
+    driver: Option<MotorDriver>,
+    torque: bool,                // wheel torque as last applied
+    arm_torque: bool,            // arm torque as last applied
+    arm_sent: Option<ArmJoints>, // last arm goal sent
+}
+
+impl Worker {
+    fn run(mut self, requests: Receiver<Message>, setpoint: Slot, reports: Sender<Report>) {
+        while let Ok(message) = requests.recv() {
+            // Setpoints come first, whatever woke the thread; only the latest matters
+            let latest = setpoint
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(setpoint) = latest
+                && let Some(result) = self.cycle(&setpoint)
+                && reports.send(Report::Cycle(result)).is_err()
+            {
+                return;
+            }
+            if let Message::Request(request) = message
+                && reports.send(self.handle(request)).is_err()
+            {
+                return;
+            }
+        }
+        debug!("Motor thread stopping");
+    }
+
+    fn handle(&mut self, request: Request) -> Report {
+        match request {
+            Request::Connect(open) => {
+                self.driver = None; // release the port before opening it again
+                let result = open().map(|driver| {
+                    self.driver = Some(driver);
+                    self.torque = true;
+                    self.arm_torque = true;
+                    self.arm_sent = None;
+                });
+                Report::Connected(result)
+            }
+            Request::EnableTorque => {
+                // Without a driver, the next connect enables torque anyway
+                self.torque = true;
+                self.arm_torque = true;
+                self.arm_sent = None; // the arm holds where it is, send the goal again
+                let result = self.driver.as_mut().map_or(Ok(()), |d| d.initialize());
+                Report::TorqueEnabled(self.check(result))
+            }
+            Request::Scan(baud_rates) => {
+                let result = match self.driver.as_mut() {
+                    Some(driver) => driver.scan(&baud_rates),
+                    None => Err(FeetechError::Io(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        "motor bus is not connected",
+                    ))),
+                };
+                Report::Scanned(self.check(result))
+            }
+            Request::Flush => Report::Flushed,
+        }
+    }
+
+    /// Apply a setpoint and read the motors back; `None` without a driver
+    fn cycle(&mut self, setpoint: &Setpoint) -> Option<Result<Readings, FeetechError>> {
+        let driver = self.driver.as_mut()?;
+        let result = Self::apply(
+            driver,
+            &mut self.torque,
+            &mut self.arm_torque,
+            &mut self.arm_sent,
+            setpoint,
+        );
+        Some(self.check(result))
+    }
+
+    fn apply(
+        driver: &mut MotorDriver,
+        torque: &mut bool,
+        arm_torque: &mut bool,
+        arm_sent: &mut Option<ArmJoints>,
+        setpoint: &Setpoint,
+    ) -> Result<Readings, FeetechError> {
+        let base = &setpoint.base;
+        driver.set_kinematics(setpoint.kinematics);
+        driver.set_body_velocity(base.x_vel, base.y_vel, base.theta_vel)?;
+        if !setpoint.torque && *torque {
+            driver.disable_torque()?;
+            *torque = false;
+        }
+        if !setpoint.arm_torque && *arm_torque {
+            driver.disable_arm_torque()?;
+            *arm_torque = false;
+        }
+        if setpoint.arm != *arm_sent {
+            if let Some(goal) = setpoint.arm {
+                driver.set_arm_positions(goal.to_array())?;
+            }
+            *arm_sent = setpoint.arm;
+        }
+
+        Ok(Readings {
+            wheels: driver.read_feedback()?,
+            commanded: driver.commanded_velocities(),
+            arm: driver.read_arm_feedback()?,
+            read_at: Instant::now(),
+        })
+    }
+
+    /// Drop the driver once the bus is gone; the control loop reconnects
+    fn check<T>(&mut self, result: Result<T, FeetechError>) -> Result<T, FeetechError> {
+        if let Err(e @ (FeetechError::Io(_) | FeetechError::Serial(_))) = &result {
+            warn!("Motor bus lost, dropping the driver: {}", e);
+            self.driver = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::BASE_MOTOR_IDS;
+    use crate::motor::feetech::{FeetechBus, Register};
+    use crate::motor::sim::SimServoBank;
+
+    fn setpoint(x_vel: f32) -> Setpoint {
+        Setpoint {
+            base: BaseActuation {
+                x_vel,
+                ..Default::default()
+            },
+            kinematics: KinematicParams::default(),
+            arm: None,
+            torque: true,
+            arm_torque: true,
+        }
+    }
+
+    fn recv(thread: &MotorThread) -> Report {
+        thread.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn test_cycles_run_on_the_thread() {
+        let bank = SimServoBank::new(&BASE_MOTOR_IDS);
+        let thread = MotorThread::spawn().unwrap();
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        let open: Opener = Box::new(move || {
+            let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+            driver.initialize()?;
+            Ok(driver)
+        });
+        assert!(thread.send(Request::Connect(open)).is_ok());
+        assert!(matches!(recv(&thread), Report::Connected(Ok(()))));
+
+        thread.send_setpoint(setpoint(0.2));
+        let Report::Cycle(Ok(readings)) = recv(&thread) else {
+            panic!("expected readings");
+        };
+        assert!(readings.wheels[0].velocity != 0);
+
+        // Releasing torque stops the wheels first
+        let mut release = setpoint(0.0);
+        release.torque = false;
+        thread.send_setpoint(release);
+        assert!(matches!(recv(&thread), Report::Cycle(Ok(_))));
+        assert_eq!(bank.peek(7, Register::TorqueEnable), Some(0));
+        assert_eq!(bank.present_velocity(7), Some(0));
+
+        // A lost bus drops the driver: further cycles report nothing
+        bank.set_connected(false);
+        thread.send_setpoint(setpoint(0.0));
+        assert!(matches!(
+            recv(&thread),
+            Report::Cycle(Err(FeetechError::Io(_)))
+        ));
+        thread.send_setpoint(setpoint(0.0));
+        assert!(thread.send(Request::Flush).is_ok());
+        assert!(matches!(recv(&thread), Report::Flushed));
+    }
+
+    #[test]
+    fn test_busy_thread_applies_the_latest_setpoint() {
+        let bank = SimServoBank::new(&BASE_MOTOR_IDS);
+        let thread = MotorThread::spawn().unwrap();
+        let bus = FeetechBus::new(Box::new(bank.transport()));
+        // The open blocks until released, keeping the thread busy meanwhile
+        let (opening_tx, opening_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel();
+        let open: Opener = Box::new(move || {
+            opening_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            let mut driver = MotorDriver::with_bus(bus, BASE_MOTOR_IDS);
+            driver.initialize()?;
+            Ok(driver)
+        });
+        assert!(thread.send(Request::Connect(open)).is_ok());
+        opening_rx.recv().unwrap();
+
+        // A stop sent with the queue full replaces the pending setpoint
+        thread.send_setpoint(setpoint(0.1));
+        let mut flushes = 0;
+        while thread.send(Request::Flush).is_ok() {
+            flushes += 1;
+        }
+        assert_eq!(flushes, QUEUE_DEPTH - 1);
+        thread.send_setpoint(setpoint(0.0));
+        release_tx.send(()).unwrap();
+
+        assert!(matches!(recv(&thread), Report::Connected(Ok(()))));
+        let Report::Cycle(Ok(readings)) = recv(&thread) else {
+            panic!("expected readings");
+        };
+        assert_eq!(readings.commanded.as_array(), [0, 0, 0]);
+        for _ in 0..flushes {
+            assert!(matches!(recv(&thread), Report::Flushed));
+        }
+    }
+}
@@ -0,0 +1,443 @@
+// Prioritised multiplexer for base commands from several sources
+//
+// Every source (teleop, autonomy, scripts, ...) publishes on its own topic
+// `<topics.cmd_base>/<name>` and has a priority and a timeout. Each tick the
+// mux picks the highest-priority source whose latest command is still fresh,
+// so a joystick can take over from autonomy and hand control back simply by
+// going quiet. The plain `topics.cmd_base` topic stays available as the
+// lowest-priority `default` source.
+//
+// Commands carrying a sequence number and a `source` sender id are dropped if
+// they are not newer than the last one accepted from the same sender, and
+// commands carrying a stamp and/or TTL are dropped once expired. A TTL can only
+// shorten how long a command stays fresh, never extend it past the source
+// timeout. A sender's last sequence number is forgotten once it has been quiet
+// for the source timeout, so a restarted sender may count from 0 again under
+// the same id. Anonymous senders cannot be told apart, so their sequence
+// numbers are not checked.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::messages::BaseCommand;
+
+/// Name of the source fed by the legacy `topics.cmd_base` topic
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// Senders whose sequence numbers are remembered per source; beyond that the
+/// least recently heard one is forgotten
+const MAX_SENDERS: usize = 64;
+
+/// A configured command source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandSource {
+    /// Topic suffix and name reported in health
+    pub name: String,
+    /// Higher wins; ties go to the source listed first
+    pub priority: u8,
+    /// Commands older than this are ignored (ms)
+    pub timeout_ms: u64,
+}
+
+impl CommandSource {
+    pub fn new(name: &str, priority: u8, timeout_ms: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            priority,
+            timeout_ms,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Built-in sources used when the config does not list any
+pub fn default_sources() -> Vec<CommandSource> {
+    vec![
+        CommandSource::new("teleop", 30, 250),
+        CommandSource::new("autonomy", 20, 500),
+        CommandSource::new("script", 10, 500),
+    ]
+}
+
+/// Why a command was dropped
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CommandRejected {
+    #[error("out of order: seq {seq} is not newer than {last}")]
+    OutOfOrder { seq: u64, last: u64 },
+
+    #[error("expired: {age_ms} ms old, lifetime {lifetime_ms} ms")]
+    Expired { age_ms: u64, lifetime_ms: u64 },
+}
+
+/// Latest command of one source
+#[derive(Debug, Clone)]
+struct Slot {
+    source: CommandSource,
+    latest: Option<(BaseCommand, Instant)>, // command and when it expires
+    last_seq: HashMap<String, (u64, Instant)>, // per sender: last seq, when accepted
+}
+
+impl Slot {
+    fn fresh_command(&self, now: Instant) -> Option<&BaseCommand> {
+        let (cmd, expires_at) = self.latest.as_ref()?;
+        (now <= *expires_at).then_some(cmd)
+    }
+
+    fn accept(
+        &mut self,
+        cmd: BaseCommand,
+        now: Instant,
+        wall_ms: u64,
+    ) -> Result<(), CommandRejected> {
+        if let Some(seq) = cmd.seq
+            && let Some(sender) = &cmd.source
+            && let Some(&(last, at)) = self.last_seq.get(sender)
+            && now.saturating_duration_since(at) <= self.source.timeout()
+            && seq <= last
+        {
+            return Err(CommandRejected::OutOfOrder { seq, last });
+        }
+
+        let lifetime_ms = cmd.ttl_ms.map_or(self.source.timeout_ms, |ttl| {
+            ttl.min(self.source.timeout_ms)
+        });
+        let age_ms = cmd
+            .stamp_ms
+            .map_or(0, |stamp| wall_ms.saturating_sub(stamp));
+        if age_ms > lifetime_ms {
+            return Err(CommandRejected::Expired {
+                age_ms,
+                lifetime_ms,
+            });
+        }
+
+        if let Some(seq) = cmd.seq
+            && let Some(sender) = &cmd.source
+        {
+            self.remember_seq(sender.clone(), seq, now);
+        }
+        let expires_at = now + Duration::from_millis(lifetime_ms - age_ms);
+        self.latest = Some((cmd, expires_at));
+        Ok(())
+    }
+
+    fn remember_seq(&mut self, sender: String, seq: u64, now: Instant) {
+        let timeout = self.source.timeout();
+        self.last_seq
+            .retain(|_, (_, at)| now.saturating_duration_since(*at) <= timeout);
+        if !self.last_seq.contains_key(&sender)
+            && self.last_seq.len() >= MAX_SENDERS
+            && let Some(oldest) = self
+                .last_seq
+                .iter()
+                .min_by_key(|(_, (_, at))| *at)
+                .map(|(sender, _)| sender.clone())
+        {
+            self.last_seq.remove(&oldest);
+        }
+        self.last_seq.insert(sender, (seq, now));
+    }
+}
+
+/// Picks the command to execute among all sources
+#[derive(Debug, Clone)]
+pub struct CommandMux {
+    slots: Vec<Slot>,
+}
+
+impl CommandMux {
+    pub fn new(sources: impl IntoIterator<Item = CommandSource>) -> Self {
+        Self {
+            slots: sources
+                .into_iter()
+                .map(|source| Slot {
+                    source,
+                    latest: None,
+                    last_seq: HashMap::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Configured sources, in the order they were given (index = source id)
+    pub fn sources(&self) -> impl Iterator<Item = &CommandSource> {
+        self.slots.iter().map(|s| &s.source)
+    }
+
+    /// Store the latest command of a source
+    ///
+    /// `wall_ms` is the current wall-clock time (ms since the Unix epoch),
+    /// compared against the command's `stamp_ms`.
+    pub fn on_command(
+        &mut self,
+        source: usize,
+        cmd: BaseCommand,
+        now: Instant,
+        wall_ms: u64,
+    ) -> Result<(), CommandRejected> {
+        match self.slots.get_mut(source) {
+            Some(slot) => slot.accept(cmd, now, wall_ms),
+            None => Ok(()),
+        }
+    }
+
+    /// Highest-priority source with a fresh command, as (index, command)
+    pub fn select(&self, now: Instant) -> Option<(usize, &BaseCommand)> {
+        let mut best: Option<(usize, &BaseCommand)> = None;
+        for (i, slot) in self.slots.iter().enumerate() {
+            if let Some(cmd) = slot.fresh_command(now)
+                && best.is_none_or(|(b, _)| slot.source.priority > self.slots[b].source.priority)
+            {
+                best = Some((i, cmd));
+            }
+        }
+        best
+    }
+
+    /// Index of a source by name
+    pub fn source_index(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|s| s.source.name == name)
+    }
+
+    /// Name of a source by index
+    pub fn source_name(&self, source: usize) -> &str {
+        &self.slots[source].source.name
+    }
+
+    /// Change the timeout of a source, applied to commands received from now on
+    pub fn set_timeout(&mut self, source: usize, timeout_ms: u64) {
+        if let Some(slot) = self.slots.get_mut(source) {
+            slot.source.timeout_ms = timeout_ms;
+        }
+    }
+
+    /// Forget every stored command (e.g. on emergency stop)
+    ///
+    /// Sequence numbers are kept, so commands sent just before stay rejected.
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            slot.latest = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL_MS: u64 = 1_700_000_000_000;
+
+    fn cmd(x_vel: f32) -> BaseCommand {
+        BaseCommand {
+            x_vel,
+            ..Default::default()
+        }
+    }
+
+    fn sequenced(x_vel: f32, source: &str, seq: u64) -> BaseCommand {
+        BaseCommand {
+            seq: Some(seq),
+            source: Some(source.to_string()),
+            ..cmd(x_vel)
+        }
+    }
+
+    fn mux() -> CommandMux {
+        CommandMux::new([
+            CommandSource::new(DEFAULT_SOURCE, 0, 250),
+            CommandSource::new("teleop", 30, 100),
+            CommandSource::new("autonomy", 20, 500),
+        ])
+    }
+
+    #[test]
+    fn test_highest_priority_fresh_source_wins() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+        assert!(mux.select(t0).is_none());
+
+        mux.on_command(0, cmd(0.1), t0, WALL_MS).unwrap();
+        mux.on_command(2, cmd(0.2), t0, WALL_MS).unwrap();
+        let (source, selected) = mux.select(t0).unwrap();
+        assert_eq!(mux.source_name(source), "autonomy");
+        assert_eq!(selected.x_vel, 0.2);
+
+        mux.on_command(1, cmd(-0.3), t0, WALL_MS).unwrap();
+        assert_eq!(mux.select(t0).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_stale_source_hands_back_control() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+        mux.on_command(1, cmd(-0.3), t0, WALL_MS).unwrap();
+        mux.on_command(2, cmd(0.2), t0, WALL_MS).unwrap();
+
+        // Teleop times out after 100 ms, autonomy takes over
+        assert_eq!(mux.select(t0 + Duration::from_millis(150)).unwrap().0, 2);
+        assert!(mux.select(t0 + Duration::from_millis(600)).is_none());
+    }
+
+    #[test]
+    fn test_equal_priority_prefers_first_source() {
+        let mut mux = CommandMux::new([
+            CommandSource::new("a", 10, 250),
+            CommandSource::new("b", 10, 250),
+        ]);
+        let t0 = Instant::now();
+        mux.on_command(1, cmd(0.1), t0, WALL_MS).unwrap();
+        mux.on_command(0, cmd(0.2), t0, WALL_MS).unwrap();
+        assert_eq!(mux.select(t0).unwrap().0, 0);
+
+        mux.clear();
+        assert!(mux.select(t0).is_none());
+    }
+
+    #[test]
+    fn test_out_of_order_commands_dropped() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+        mux.on_command(1, sequenced(0.1, "pad", 5), t0, WALL_MS)
+            .unwrap();
+        assert_eq!(
+            mux.on_command(1, sequenced(0.2, "pad", 4), t0, WALL_MS),
+            Err(CommandRejected::OutOfOrder { seq: 4, last: 5 })
+        );
+        assert!(
+            mux.on_command(1, sequenced(0.2, "pad", 5), t0, WALL_MS)
+                .is_err()
+        );
+        assert_eq!(mux.select(t0).unwrap().1.x_vel, 0.1);
+
+        // Another sender has its own sequence, and interleaving does not
+        // reset the first one's
+        mux.on_command(1, sequenced(0.3, "web", 1), t0, WALL_MS)
+            .unwrap();
+        assert!(
+            mux.on_command(1, sequenced(0.2, "pad", 4), t0, WALL_MS)
+                .is_err()
+        );
+        mux.on_command(1, sequenced(0.4, "pad", 6), t0, WALL_MS)
+            .unwrap();
+
+        // A delayed packet cannot resume motion after an emergency stop cleared the source
+        let soon = t0 + Duration::from_millis(50);
+        mux.clear();
+        assert!(
+            mux.on_command(1, sequenced(0.3, "pad", 6), soon, WALL_MS)
+                .is_err()
+        );
+        assert!(mux.select(soon).is_none());
+
+        // After the 100 ms teleop timeout, a restarted sender counts from 0 again
+        let later = t0 + Duration::from_millis(200);
+        mux.on_command(1, sequenced(0.5, "web", 0), later, WALL_MS)
+            .unwrap();
+        assert_eq!(mux.select(later).unwrap().1.x_vel, 0.5);
+    }
+
+    #[test]
+    fn test_anonymous_senders_do_not_share_a_sequence() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+        let anonymous = |x_vel, seq| BaseCommand {
+            seq: Some(seq),
+            ..cmd(x_vel)
+        };
+        mux.on_command(2, anonymous(0.1, 9), t0, WALL_MS).unwrap();
+        mux.on_command(2, anonymous(0.2, 1), t0, WALL_MS).unwrap();
+        assert_eq!(mux.select(t0).unwrap().1.x_vel, 0.2);
+    }
+
+    #[test]
+    fn test_sender_table_is_bounded() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+        for i in 0..=MAX_SENDERS as u64 {
+            let at = t0 + Duration::from_millis(i);
+            mux.on_command(2, sequenced(0.1, &format!("s{}", i), 5), at, WALL_MS)
+                .unwrap();
+        }
+        assert_eq!(mux.slots[2].last_seq.len(), MAX_SENDERS);
+        // The least recently heard sender was forgotten, the others were not
+        let at = t0 + Duration::from_millis(100);
+        assert!(
+            mux.on_command(2, sequenced(0.1, "s0", 5), at, WALL_MS)
+                .is_ok()
+        );
+        assert!(
+            mux.on_command(2, sequenced(0.1, "s2", 5), at, WALL_MS)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ttl_and_stamp_expiry() {
+        let mut mux = mux();
+        let t0 = Instant::now();
+
+        // TTL shorter than the 500 ms autonomy timeout
+        let short = BaseCommand {
+            ttl_ms: Some(50),
+            ..cmd(0.2)
+        };
+        mux.on_command(2, short, t0, WALL_MS).unwrap();
+        assert!(mux.select(t0 + Duration::from_millis(40)).is_some());
+        assert!(mux.select(t0 + Duration::from_millis(60)).is_none());
+
+        // A TTL never extends the source timeout
+        let long = BaseCommand {
+            ttl_ms: Some(10_000),
+            ..cmd(0.2)
+        };
+        mux.on_command(2, long, t0, WALL_MS).unwrap();
+        assert!(mux.select(t0 + Duration::from_millis(600)).is_none());
+
+        // Stamped 80 ms ago with a 100 ms TTL: 20 ms left
+        let stamped = BaseCommand {
+            stamp_ms: Some(WALL_MS - 80),
+            ttl_ms: Some(100),
+            ..cmd(0.2)
+        };
+        mux.on_command(2, stamped, t0, WALL_MS).unwrap();
+        assert!(mux.select(t0 + Duration::from_millis(10)).is_some());
+        assert!(mux.select(t0 + Duration::from_millis(30)).is_none());
+
+        let expired = BaseCommand {
+            stamp_ms: Some(WALL_MS - 120),
+            ttl_ms: Some(100),
+            ..cmd(0.2)
+        };
+        assert_eq!(
+            mux.on_command(2, expired, t0, WALL_MS),
+            Err(CommandRejected::Expired {
+                age_ms: 120,
+                lifetime_ms: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_old_three_field_json_accepted() {
+        let cmd: BaseCommand =
+            serde_json::from_str(r#"{"x_vel": 0.1, "y_vel": 0.0, "theta_vel": 5.0}"#).unwrap();
+        assert_eq!(cmd.seq, None);
+        assert_eq!(cmd.ttl_ms, None);
+
+        let cmd: BaseCommand = serde_json::from_str(
+            r#"{"x_vel": 0.1, "y_vel": 0.0, "theta_vel": 5.0, "seq": 3, "source": "pad", "stamp_ms": 12, "ttl_ms": 100}"#,
+        )
+        .unwrap();
+        assert_eq!(cmd.seq, Some(3));
+        assert_eq!(cmd.source.as_deref(), Some("pad"));
+        assert_eq!(
+            serde_json::to_string(&BaseCommand::default()).unwrap(),
+            r#"{"x_vel":0.0,"y_vel":0.0,"theta_vel":0.0}"#
+        );
+    }
+}
@@ -0,0 +1,109 @@
+// Wheel odometry: integrates body velocity from forward kinematics into a 2D pose
+
+use crate::messages::BaseOdometry;
+use crate::motor::kinematics::BodyVelocity;
+
+/// Dead-reckoned pose of the base
+#[derive(Debug, Clone, Default)]
+pub struct Odometry {
+    x: f32,     // m
+    y: f32,     // m
+    theta: f32, // deg
+    twist: BodyVelocity,
+}
+
+impl Odometry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Integrate a measured body velocity over `dt` seconds
+    pub fn update(&mut self, twist: BodyVelocity, dt: f32) {
+        // Midpoint heading: exact for constant twist over short intervals
+        let heading = (self.theta + twist.theta * dt / 2.0).to_radians();
+        let (sin, cos) = heading.sin_cos();
+
+        self.x += (twist.x * cos - twist.y * sin) * dt;
+        self.y += (twist.x * sin + twist.y * cos) * dt;
+        self.theta = wrap_degrees(self.theta + twist.theta * dt);
+        self.twist = twist;
+    }
+
+    /// Reset the pose to the origin
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Current pose and twist as a message
+    pub fn message(&self) -> BaseOdometry {
+        BaseOdometry {
+            x: self.x,
+            y: self.y,
+            theta: self.theta,
+            x_vel: self.twist.x,
+            y_vel: self.twist.y,
+            theta_vel: self.twist.theta,
+        }
+    }
+}
+
+/// Wrap an angle in degrees to (-180, 180]
+fn wrap_degrees(deg: f32) -> f32 {
+    let wrapped = deg.rem_euclid(360.0);
+    if wrapped > 180.0 {
+        wrapped - 360.0
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn twist(x: f32, y: f32, theta: f32) -> BodyVelocity {
+        BodyVelocity { x, y, theta }
+    }
+
+    #[test]
+    fn test_straight_line() {
+        let mut odom = Odometry::new();
+        for _ in 0..50 {
+            odom.update(twist(0.1, 0.0, 0.0), 0.02);
+        }
+        let msg = odom.message();
+        assert!((msg.x - 0.1).abs() < 1e-4);
+        assert!(msg.y.abs() < 1e-6);
+        assert_eq!(msg.x_vel, 0.1);
+    }
+
+    #[test]
+    fn test_body_frame_is_rotated() {
+        let mut odom = Odometry::new();
+        // Turn 90° in place, then drive "forward" which is now +y
+        odom.update(twist(0.0, 0.0, 90.0), 1.0);
+        odom.update(twist(0.2, 0.0, 0.0), 1.0);
+        let msg = odom.message();
+        assert!((msg.theta - 90.0).abs() < 1e-4);
+        assert!(msg.x.abs() < 1e-4);
+        assert!((msg.y - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_full_circle_returns_home() {
+        let mut odom = Odometry::new();
+        for _ in 0..400 {
+            odom.update(twist(0.2, 0.0, 45.0), 0.02); // 8 s at 45 deg/s = 360°
+        }
+        let msg = odom.message();
+        assert!(msg.x.abs() < 1e-3 && msg.y.abs() < 1e-3, "{:?}", msg);
+    }
+
+    #[test]
+    fn test_wrap_degrees() {
+        assert_eq!(wrap_degrees(190.0), -170.0);
+        assert_eq!(wrap_degrees(-190.0), 170.0);
+        assert_eq!(wrap_degrees(180.0), 180.0);
+        assert_eq!(wrap_degrees(0.0), 0.0);
+    }
+}
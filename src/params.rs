@@ -0,0 +1,237 @@
+// Live runtime parameters
+//
+// A subset of the runtime config can be changed while the runtime is running,
+// addressed by its dotted config key (e.g. `limits.max_x_vel`). A new value
+// must have the type of the config field, keep the whole config valid, and
+// stay on the safe side of the value the runtime was started with: speed
+// limits and the watchdog timeout can be lowered but not raised, stopping
+// deceleration can be raised but not lowered, and the kinematics cannot
+// change so that the same commanded speed turns the wheels faster (a smaller
+// wheel radius or a larger base radius). Restart the runtime
+// with a new config file to go beyond the startup values.
+
+use serde_json::Value;
+
+use crate::config::{ConfigError, RuntimeConfig};
+
+/// Direction a parameter may move away from its startup value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    AtMost,
+    AtLeast,
+    Free,
+}
+
+/// Parameters that can be changed at runtime
+const PARAMS: [(&str, Bound); 22] = [
+    ("cmd_timeout_ms", Bound::AtMost),
+    ("limits.max_x_vel", Bound::AtMost),
+    ("limits.max_y_vel", Bound::AtMost),
+    ("limits.max_theta_vel", Bound::AtMost),
+    ("accel.x.accel", Bound::AtMost),
+    ("accel.x.decel", Bound::AtLeast),
+    ("accel.x.estop_decel", Bound::AtLeast),
+    ("accel.x.jerk", Bound::Free),
+    ("accel.y.accel", Bound::AtMost),
+    ("accel.y.decel", Bound::AtLeast),
+    ("accel.y.estop_decel", Bound::AtLeast),
+    ("accel.y.jerk", Bound::Free),
+    ("accel.theta.accel", Bound::AtMost),
+    ("accel.theta.decel", Bound::AtLeast),
+    ("accel.theta.estop_decel", Bound::AtLeast),
+    ("accel.theta.jerk", Bound::Free),
+    ("kinematics.wheel_radius", Bound::AtLeast),
+    ("kinematics.base_radius", Bound::AtMost),
+    ("kinematics.max_raw", Bound::AtMost),
+    ("health.max_temperature", Bound::AtMost),
+    ("health.min_voltage", Bound::AtLeast),
+    ("motor.reconnect_interval_ms", Bound::Free),
+];
+
+/// Error reading or changing a parameter
+#[derive(Debug, thiserror::Error)]
+pub enum ParamError {
+    #[error("Unknown parameter `{name}`")]
+    Unknown { name: String },
+
+    #[error("`{name}` cannot be changed at runtime")]
+    NotLive { name: String },
+
+    #[error("Invalid value for `{name}`: {reason}")]
+    Invalid { name: String, reason: String },
+
+    #[error("Unsafe value for `{name}`: must be {bound} the startup value {limit}")]
+    Unsafe {
+        name: String,
+        bound: &'static str,
+        limit: Value,
+    },
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+/// Names of all live parameters
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PARAMS.iter().map(|&(name, _)| name)
+}
+
+/// Current value of a live parameter (`null` for an unset optional value)
+pub fn get(config: &RuntimeConfig, name: &str) -> Result<Value, ParamError> {
+    bound(config, name)?;
+    Ok(lookup(&to_tree(config), name)
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+/// Config with a live parameter changed, or why the change is refused
+///
+/// `startup` is the config the runtime was started with, bounding how far
+/// safety-related parameters may move.
+pub fn set(
+    config: &RuntimeConfig,
+    startup: &RuntimeConfig,
+    name: &str,
+    value: Value,
+) -> Result<RuntimeConfig, ParamError> {
+    let bound = bound(config, name)?;
+
+    let mut tree = to_tree(config);
+    // Optional values may be missing from the tree, so insert into the parent table
+    let (parent, key) = name.rsplit_once('.').unwrap_or(("", name));
+    parent
+        .split('.')
+        .filter(|k| !k.is_empty())
+        .try_fold(&mut tree, |v, k| v.get_mut(k))
+        .and_then(Value::as_object_mut)
+        .expect("live parameters are config keys")
+        .insert(key.to_string(), value.clone());
+    let updated: RuntimeConfig = serde_json::from_value(tree).map_err(|e| ParamError::Invalid {
+        name: name.to_string(),
+        reason: e.to_string(),
+    })?;
+
+    let limit = get(startup, name)?;
+    if let (Some(new), Some(old)) = (value.as_f64(), limit.as_f64()) {
+        let unsafe_bound = match bound {
+            Bound::AtMost if new > old => Some("at most"),
+            Bound::AtLeast if new < old => Some("at least"),
+            _ => None,
+        };
+        if let Some(bound) = unsafe_bound {
+            return Err(ParamError::Unsafe {
+                name: name.to_string(),
+                bound,
+                limit,
+            });
+        }
+    }
+
+    updated.validate()?;
+    Ok(updated)
+}
+
+fn bound(config: &RuntimeConfig, name: &str) -> Result<Bound, ParamError> {
+    if let Some(&(_, bound)) = PARAMS.iter().find(|(n, _)| *n == name) {
+        Ok(bound)
+    } else if lookup(&to_tree(config), name).is_some() {
+        Err(ParamError::NotLive {
+            name: name.to_string(),
+        })
+    } else {
+        Err(ParamError::Unknown {
+            name: name.to_string(),
+        })
+    }
+}
+
+fn to_tree(config: &RuntimeConfig) -> Value {
+    // Going through text keeps f32 values short (0.2, not 0.20000000298023224)
+    let text = serde_json::to_string(config).expect("runtime config is always serializable");
+    serde_json::from_str(&text).expect("serialized config is valid JSON")
+}
+
+fn lookup<'a>(tree: &'a Value, name: &str) -> Option<&'a Value> {
+    name.split('.').try_fold(tree, |v, k| v.get(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_every_param_can_be_read() {
+        let config = RuntimeConfig::default();
+        for name in names() {
+            get(&config, name).unwrap();
+        }
+        assert_eq!(get(&config, "limits.max_x_vel").unwrap(), json!(0.5));
+        assert_eq!(get(&config, "accel.x.jerk").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_set_lowers_limit() {
+        let startup = RuntimeConfig::default();
+        let config = set(&startup, &startup, "limits.max_x_vel", json!(0.25)).unwrap();
+        assert_eq!(config.limits.max_x_vel, 0.25);
+
+        // Back up to the startup value is fine
+        let config = set(&config, &startup, "limits.max_x_vel", json!(0.5)).unwrap();
+        assert_eq!(config.limits, startup.limits);
+
+        let config = set(&config, &startup, "accel.theta.jerk", json!(2000.0)).unwrap();
+        assert_eq!(config.accel.theta.jerk, Some(2000.0));
+        let config = set(&config, &startup, "accel.theta.jerk", Value::Null).unwrap();
+        assert_eq!(config.accel.theta.jerk, None);
+    }
+
+    #[test]
+    fn test_set_rejects_unsafe_values() {
+        let startup = RuntimeConfig::default();
+        assert!(matches!(
+            set(&startup, &startup, "limits.max_x_vel", json!(1.0)),
+            Err(ParamError::Unsafe { .. })
+        ));
+        for (name, value) in [
+            ("accel.x.estop_decel", json!(0.1)),
+            ("accel.theta.decel", json!(10.0)),
+            ("kinematics.wheel_radius", json!(0.01)),
+            ("kinematics.base_radius", json!(0.5)),
+        ] {
+            assert!(
+                matches!(
+                    set(&startup, &startup, name, value),
+                    Err(ParamError::Unsafe { .. })
+                ),
+                "{}",
+                name
+            );
+        }
+        assert!(matches!(
+            set(&startup, &startup, "limits.max_y_vel", json!(-0.1)),
+            Err(ParamError::Config(_))
+        ));
+        assert!(matches!(
+            set(&startup, &startup, "cmd_timeout_ms", json!(1)),
+            Err(ParamError::Config(_))
+        ));
+        assert!(matches!(
+            set(&startup, &startup, "health.max_temperature", json!("hot")),
+            Err(ParamError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_rejects_unknown_and_static_params() {
+        let config = RuntimeConfig::default();
+        assert!(matches!(
+            set(&config, &config, "motor.port", json!("/dev/null")),
+            Err(ParamError::NotLive { .. })
+        ));
+        assert!(matches!(
+            get(&config, "limits.max_z_vel"),
+            Err(ParamError::Unknown { .. })
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+// Recording of runtime traffic to JSON Lines files
+//
+// Each line is one `Record`: a message the runtime received or published, the
+// topic it travelled on and when. Recordings can be inspected with standard
+// tools (`jq`, `grep`) and republished with the `replay` example.
+//
+// Records are written by a thread of their own, so a slow disk never delays
+// the control loop: the loop only queues them.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::codec::{CodecError, WireEncoding};
+use crate::messages::{
+    ArmCommand, ArmState, BaseActuation, BaseCommand, EstopCommand, RuntimeHealth, WheelState,
+    wall_clock_ms,
+};
+
+/// Records waiting for the writer thread; further ones are dropped
+const QUEUE_DEPTH: usize = 1024;
+
+/// Longest time a written record stays in the write buffer
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A recorded message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", content = "msg", rename_all = "snake_case")]
+pub enum Entry {
+    Command(BaseCommand),
+    Estop(EstopCommand),
+    Actuation(BaseActuation),
+    Health(RuntimeHealth),
+    Wheels(WheelState),
+    Arm(ArmCommand),
+    ArmState(ArmState),
+}
+
+impl Entry {
+    /// Name used in the `kind` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Entry::Command(_) => "command",
+            Entry::Estop(_) => "estop",
+            Entry::Actuation(_) => "actuation",
+            Entry::Health(_) => "health",
+            Entry::Wheels(_) => "wheels",
+            Entry::Arm(_) => "arm",
+            Entry::ArmState(_) => "arm_state",
+        }
+    }
+
+    /// Encode the message itself, as it travelled on its topic
+    pub fn encode(&self, encoding: WireEncoding) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Entry::Command(msg) => encoding.encode(msg),
+            Entry::Estop(msg) => encoding.encode(msg),
+            Entry::Actuation(msg) => encoding.encode(msg),
+            Entry::Health(msg) => encoding.encode(msg),
+            Entry::Wheels(msg) => encoding.encode(msg),
+            Entry::Arm(msg) => encoding.encode(msg),
+            Entry::ArmState(msg) => encoding.encode(msg),
+        }
+    }
+}
+
+/// One line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Record {
+    pub t_ms: u64,    // since the recording started
+    pub wall_ms: u64, // ms since the Unix epoch
+    pub topic: String,
+    #[serde(flatten)]
+    pub entry: Entry,
+}
+
+impl Record {
+    /// Offset from the start of the recording
+    pub fn offset(&self) -> Duration {
+        Duration::from_millis(self.t_ms)
+    }
+}
+
+/// Error reading a recording
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("Failed to read recording {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid record on line {line} of {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// Appends records to a JSON Lines file from a writer thread
+///
+/// Dropping the recorder writes the remaining records; `finish` does the same
+/// and reports whether that worked.
+pub struct Recorder {
+    records: Option<SyncSender<Record>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+    started_at: Instant,
+    dropped: u64,
+}
+
+impl Recorder {
+    /// Create (or truncate) a recording file
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let (records, queue) = mpsc::sync_channel(QUEUE_DEPTH);
+        let writer = thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || write_records(file, queue))?;
+        Ok(Self {
+            records: Some(records),
+            writer: Some(writer),
+            started_at: Instant::now(),
+            dropped: 0,
+        })
+    }
+
+    /// Queue a record without waiting for it to be written
+    ///
+    /// Fails with the writer's error once the file could not be written.
+    pub fn record(&mut self, topic: &str, entry: Entry) -> io::Result<()> {
+        let record = Record {
+            t_ms: self.started_at.elapsed().as_millis() as u64,
+            wall_ms: wall_clock_ms(),
+            topic: topic.to_string(),
+            entry,
+        };
+        let Some(records) = &self.records else {
+            return Err(io::Error::other("recording already finished"));
+        };
+        match records.try_send(record) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                if self.dropped.is_power_of_two() {
+                    warn!("Recorder falling behind, {} records dropped", self.dropped);
+                }
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => match self.close() {
+                Ok(()) => Err(io::Error::other("recorder thread stopped")),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    /// Write the queued records and close the file
+    pub fn finish(mut self) -> io::Result<()> {
+        self.close()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        // Closing the queue ends the thread once it wrote everything queued
+        self.records = None;
+        match self.writer.take() {
+            Some(writer) => writer
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("recorder thread panicked"))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!("Recording failed: {}", e);
+        }
+    }
+}
+
+/// Writer thread: write queued records, flushing at least every `FLUSH_INTERVAL`
+fn write_records(file: File, queue: Receiver<Record>) -> io::Result<()> {
+    let mut writer = BufWriter::new(file);
+    let mut flushed_at = Instant::now();
+    loop {
+        match queue.recv_timeout(FLUSH_INTERVAL) {
+            Ok(record) => {
+                serde_json::to_writer(&mut writer, &record)?;
+                writer.write_all(b"\n")?;
+                if flushed_at.elapsed() >= FLUSH_INTERVAL {
+                    writer.flush()?;
+                    flushed_at = Instant::now();
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                writer.flush()?;
+                flushed_at = Instant::now();
+            }
+            Err(RecvTimeoutError::Disconnected) => return writer.flush(),
+        }
+    }
+}
+
+/// Read every record of a recording, skipping blank lines
+pub fn read_recording(path: &Path) -> Result<Vec<Record>, RecordingError> {
+    let io_error = |source| RecordingError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let reader = BufReader::new(File::open(path).map_err(io_error)?);
+
+    let mut records = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|source| RecordingError::Parse {
+            path: path.to_path_buf(),
+            line: i + 1,
+            source,
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// When to replay a record, relative to the start of the replay
+///
+/// `speed` scales playback: 2.0 replays twice as fast, 0.5 at half speed.
+pub fn replay_offset(record: &Record, speed: f64) -> Duration {
+    record.offset().div_f64(speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_back() {
+        let path = std::env::temp_dir().join(format!("lekiwi-rec-{}.jsonl", std::process::id()));
+        let cmd = BaseCommand {
+            x_vel: 0.1,
+            seq: Some(3),
+            ..Default::default()
+        };
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder
+            .record("lekiwi/cmd/base/teleop", Entry::Command(cmd.clone()))
+            .unwrap();
+        recorder
+            .record(
+                "lekiwi/state/health",
+                Entry::Health(RuntimeHealth::default()),
+            )
+            .unwrap();
+        recorder.finish().unwrap();
+
+        let records = read_recording(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].topic, "lekiwi/cmd/base/teleop");
+        assert_eq!(records[0].entry, Entry::Command(cmd));
+        assert!(records[0].t_ms <= records[1].t_ms);
+        assert!(records[1].wall_ms > 0);
+    }
+
+    #[test]
+    fn test_record_format() {
+        let record = Record {
+            t_ms: 20,
+            wall_ms: 1_700_000_000_000,
+            topic: "lekiwi/rt/base".to_string(),
+            entry: Entry::Actuation(BaseActuation::default()),
+        };
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"t_ms":20,"wall_ms":1700000000000,"topic":"lekiwi/rt/base","kind":"actuation","msg":{"x_vel":0.0,"y_vel":0.0,"theta_vel":0.0}}"#
+        );
+        assert_eq!(replay_offset(&record, 2.0), Duration::from_millis(10));
+        assert_eq!(
+            record.entry.encode(WireEncoding::Json).unwrap(),
+            br#"{"x_vel":0.0,"y_vel":0.0,"theta_vel":0.0}"#
+        );
+    }
+}
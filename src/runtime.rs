@@ -1,16 +1,31 @@
 // 50 Hz loop with watchdog and motor control
 
 use clap::Parser;
+use serde::Serialize;
+use std::io;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use tokio::time::interval;
 use tracing::{debug, error, info, warn}; // better logging (emits events into the void, not stdout - and a subscriber (tracing-subscriber) can listen to them)
 
 // local imports
-use crate::config::{
-    CMD_TIMEOUT, LOOP_HZ, MOTOR_ENABLED, MOTOR_PORT, TOPIC_CMD_BASE, TOPIC_HEALTH, TOPIC_RT_BASE,
+use crate::codec;
+use crate::config::{ConfigError, EncodingConfig, RuntimeConfig};
+use crate::limiter::RateLimiter;
+use crate::messages::{
+    ArmCommand, ArmJoints, ArmState, BaseActuation, BaseCommand, BaseOdometry, BuildInfo,
+    EstopCommand, HealthReason, LastError, MotorState, Param, ParamChange, ParamRequest,
+    RuntimeHealth, RuntimeInfo, ScanRequest, WheelFeedback, WheelState, wall_clock_ms,
 };
-use crate::messages::{BaseActuation, BaseCommand, RuntimeHealth};
-use crate::motor::MotorDriver;
+use crate::motor::feetech::{BAUD_RATES, BusInventory, FeetechError, baud_rate_index};
+use crate::motor::kinematics::{WheelVelocities, wheel_raw_to_body_with_params};
+use crate::motor::{ArmDriver, Calibration, MotorDriver, MotorFeedback};
+use crate::motor_thread::{MotorThread, Opener, Readings, Report, Request, Setpoint};
+use crate::mux::{CommandMux, DEFAULT_SOURCE};
+use crate::odometry::Odometry;
+use crate::params::{self, ParamError};
+use crate::recorder::{Entry, Recorder};
+use crate::simulation::SimBase;
 
 #[derive(Parser)]
 #[command(name = "lekiwi-runtime")]
@@ -22,91 +37,878 @@ pub struct Args {
     /// TCP port to listen on (default: 7447)
     #[arg(long, default_value = "7447")]
     pub port: u16,
+
+    /// Runtime config file (TOML, or JSON5 for .json/.json5 files)
+    #[arg(long, env = "LEKIWI_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Serial port for the motor bus (overrides `motor.port`)
+    #[arg(long, env = "LEKIWI_MOTOR_PORT")]
+    pub motor_port: Option<String>,
+
+    /// Motor calibration file (overrides `motor.calibration`)
+    #[arg(long, env = "LEKIWI_CALIBRATION")]
+    pub calibration: Option<PathBuf>,
+
+    /// Run without motor hardware (overrides `motor.enabled`)
+    #[arg(long, env = "LEKIWI_NO_MOTORS")]
+    pub no_motors: bool,
+
+    /// Drive a simulated base instead of the motor bus (overrides `sim.enabled`)
+    #[arg(long, env = "LEKIWI_SIM")]
+    pub sim: bool,
+
+    /// Control loop frequency in Hz (overrides `loop_hz`)
+    #[arg(long, env = "LEKIWI_LOOP_HZ")]
+    pub loop_hz: Option<u64>,
+
+    /// Watchdog timeout in ms (overrides `cmd_timeout_ms`)
+    #[arg(long, env = "LEKIWI_CMD_TIMEOUT_MS")]
+    pub cmd_timeout_ms: Option<u64>,
+
+    /// Record received commands, actuation, health and wheel telemetry to this JSON Lines file
+    #[arg(long, env = "LEKIWI_RECORD")]
+    pub record: Option<PathBuf>,
+}
+
+impl Args {
+    /// Build the effective config: defaults < config file < env < CLI flags
+    pub fn load_config(&self) -> Result<RuntimeConfig, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => RuntimeConfig::from_file(path)?,
+            None => RuntimeConfig::default(),
+        };
+
+        if let Some(port) = &self.motor_port {
+            config.motor.port = port.clone();
+        }
+        if let Some(path) = &self.calibration {
+            config.motor.calibration = Some(path.clone());
+        }
+        if self.no_motors {
+            config.motor.enabled = false;
+        }
+        if self.sim {
+            config.sim.enabled = true;
+        }
+        if let Some(hz) = self.loop_hz {
+            config.loop_hz = hz;
+        }
+        if let Some(ms) = self.cmd_timeout_ms {
+            config.cmd_timeout_ms = ms;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Retry schedule while the motor bus is offline
+#[derive(Debug, Clone, Copy)]
+struct Reconnect {
+    attempts: u32, // failed attempts since the bus was lost
+    next_attempt: Instant,
+}
+
+/// Latched emergency stop
+#[derive(Debug, Clone, Copy)]
+struct Estop {
+    torque_disabled: bool,
+    arm_released: bool,
+    resetting: bool, // waiting for the motor thread to re-enable torque
+}
+
+/// Where the motor driver stands, as last reported by the motor thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MotorLink {
+    Down,
+    Connecting,
+    Ready,
+}
+
+/// How long to wait for the motors to open and initialize at startup
+const INIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long shutdown waits for the motor thread to stop the base
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Ticks without a motor report before the motor I/O counts as stalled
+const STALL_TICKS: u32 = 10;
+
+/// Least time between warnings about dropped base commands
+const DROP_WARN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Record a message, stopping the recording if the file cannot be written
+fn record(recorder: &mut Option<Recorder>, topic: &str, entry: Entry) {
+    if let Some(r) = recorder.as_mut()
+        && let Err(e) = r.record(topic, entry)
+    {
+        error!("Recording failed, stopping it: {}", e);
+        *recorder = None;
+    }
+}
+
+/// Answer a query with a value, or with the reason the request failed
+///
+/// Replies in JSON unless the selector asks for `?encoding=cbor`. Never fails:
+/// a value that cannot be encoded gets an error reply, and a reply that
+/// cannot be sent is only logged.
+async fn reply<T: Serialize>(query: &zenoh::query::Query, what: &str, result: Result<&T, &str>) {
+    let encoding = codec::reply_encoding(query);
+    let payload = result.map_err(str::to_string).and_then(|value| {
+        encoding.encode(value).map_err(|e| {
+            error!("Failed to encode {} reply: {}", what, e);
+            e.to_string()
+        })
+    });
+    let sent = match payload {
+        Ok(payload) => {
+            query
+                .reply(query.key_expr().clone(), payload)
+                .encoding(encoding.zenoh_encoding())
+                .await
+        }
+        Err(message) => {
+            let payload = encoding
+                .encode(&message)
+                .unwrap_or_else(|_| message.into_bytes());
+            query
+                .reply_err(payload)
+                .encoding(encoding.zenoh_encoding())
+                .await
+        }
+    };
+    if let Err(e) = sent {
+        warn!("Failed to reply to {} query: {}", what, e);
+    }
 }
 
 pub struct Runtime {
-    latest_cmd: Option<BaseCommand>,
-    cmd_received_at: Instant,
-    health: RuntimeHealth,
-    motor_driver: Option<MotorDriver>,
+    config: RuntimeConfig,
+    startup_config: RuntimeConfig, // bounds live parameter changes
+    staged_params: Option<(RuntimeConfig, Vec<String>)>, // applied next tick, with changed names
+    started_at: Instant,
+    mux: CommandMux,
+    active_source: Option<usize>, // mux source driving the base
+    cmd_stale: bool,
+    estop: Option<Estop>,          // set while the emergency stop is latched
+    motor_io: Option<MotorThread>, // owns the motor driver; started on the first connect
+    motor_link: MotorLink,
+    motor_reported_at: Instant, // last report from the motor thread
+    readings: Option<Readings>, // received since the last tick
+    scan_pending: bool,
+    scan_result: Option<Result<BusInventory, String>>,
+    calibration: Calibration,    // applied to every (re)initialized driver
+    arm_goal: Option<ArmJoints>, // latest arm command, sent with every setpoint
+    sim: Option<SimBase>,        // replaces the motor bus in --sim mode
+    motor_faults: Vec<HealthReason>, // from the latest motor I/O
+    reconnect: Option<Reconnect>, // set while motors are offline
+    last_error: Option<(String, u64)>, // message and wall-clock time (ms)
+    connect_error: Option<FeetechError>, // latest failed connect, for init_motors
+    odometry: Odometry,
+    odom_updated_at: Option<Instant>,
+    limiter: RateLimiter,
+    actuated_at: Instant,
+    sent_base: BaseActuation, // last base setpoint handed to the motor thread
+    dropped_cmds: u64,        // rejected by the mux since the last warning
+    dropped_warned_at: Option<Instant>,
 }
 
 impl Runtime {
-    pub fn new() -> Self {
+    pub fn new(config: RuntimeConfig) -> Self {
         Self {
-            latest_cmd: None,
-            cmd_received_at: Instant::now(),
-            health: RuntimeHealth::CmdStale, // Start stale until first cmd
-            motor_driver: None,
+            mux: CommandMux::new(config.command_sources()),
+            active_source: None,
+            sim: config
+                .sim
+                .enabled
+                .then(|| SimBase::new(&config.sim, config.motor.ids, &config.arm_ids())),
+            startup_config: config.clone(),
+            staged_params: None,
+            config,
+            started_at: Instant::now(),
+            cmd_stale: true, // Start stale until first cmd
+            estop: None,
+            motor_io: None,
+            motor_link: MotorLink::Down,
+            motor_reported_at: Instant::now(),
+            readings: None,
+            scan_pending: false,
+            scan_result: None,
+            calibration: Calibration::default(),
+            arm_goal: None,
+            motor_faults: Vec::new(),
+            reconnect: None,
+            last_error: None,
+            connect_error: None,
+            odometry: Odometry::new(),
+            odom_updated_at: None,
+            limiter: RateLimiter::new(),
+            actuated_at: Instant::now(),
+            sent_base: BaseActuation::default(),
+            dropped_cmds: 0,
+            dropped_warned_at: None,
         }
     }
 
-    /// Initialize motor driver
-    pub fn init_motors(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if !MOTOR_ENABLED {
-            info!("Motor control disabled in config");
+    /// Open and initialize the motors, waiting for the outcome (at startup)
+    ///
+    /// A failure is kept as a motor fault so it shows up in the health status,
+    /// and schedules another attempt after `motor.reconnect_interval_ms`.
+    pub fn init_motors(&mut self) -> Result<(), FeetechError> {
+        self.connect_error = None;
+        if !self.connect_motors() {
             return Ok(());
         }
+        self.sync_motors(INIT_TIMEOUT);
+        match self.motor_link {
+            MotorLink::Ready => Ok(()),
+            MotorLink::Down => Err(self.connect_error.take().unwrap_or_else(|| {
+                FeetechError::Io(io::Error::other("motors failed to initialize"))
+            })),
+            MotorLink::Connecting => Err(FeetechError::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("motors did not initialize within {:?}", INIT_TIMEOUT),
+            ))),
+        }
+    }
 
-        info!("Initializing motor driver on {}...", MOTOR_PORT);
-        let mut driver = MotorDriver::new(MOTOR_PORT)?;
-        driver.initialize()?;
-        self.motor_driver = Some(driver);
-        info!("Motor driver initialized successfully");
-        Ok(())
+    /// Ask the motor thread to open and initialize the motors
+    ///
+    /// Returns `false` when motor control is disabled.
+    fn connect_motors(&mut self) -> bool {
+        let motor = &self.config.motor;
+        let (ids, port) = (motor.ids, motor.port.clone());
+        let open: Opener = match &self.sim {
+            Some(sim) => {
+                info!("Initializing motor driver on the simulated base...");
+                let bus = sim.bus();
+                Box::new(move || Ok(MotorDriver::with_bus(bus, ids)))
+            }
+            None if !motor.enabled => {
+                info!("Motor control disabled in config");
+                return false;
+            }
+            None => {
+                info!("Initializing motor driver on {}...", port);
+                Box::new(move || MotorDriver::with_motor_ids(&port, ids))
+            }
+        };
+        let kinematics = self.config.kinematics;
+        let calibration = self.calibration.clone();
+        let arm = self.config.arm.enabled.then_some(self.config.arm.ids);
+        self.connect(Box::new(move || {
+            let mut driver = open()?;
+            driver.set_kinematics(kinematics);
+            driver.set_calibration(calibration);
+            if let Some(ids) = arm {
+                driver.set_arm(ArmDriver::new(ids));
+            }
+            driver.initialize()?;
+            Ok(driver)
+        }));
+        true
+    }
+
+    /// Hand a driver opener to the motor thread, starting the thread if needed
+    fn connect(&mut self, open: Opener) {
+        if self.motor_io.is_none() {
+            match MotorThread::spawn() {
+                Ok(thread) => self.motor_io = Some(thread),
+                Err(e) => {
+                    error!("Failed to start the motor thread: {}", e);
+                    let fault = HealthReason::BusError {
+                        error: e.to_string(),
+                    };
+                    self.on_connect_failed(FeetechError::Io(e), fault);
+                    return;
+                }
+            }
+        }
+        let io = self.motor_io.as_ref().expect("started above");
+        match io.send(Request::Connect(open)) {
+            Ok(()) => self.motor_link = MotorLink::Connecting,
+            Err(_) => debug!("Motor thread busy, connecting on a later tick"),
+        }
+    }
+
+    /// Keep a failed connect attempt as a motor fault and schedule the next one
+    fn on_connect_failed(&mut self, e: FeetechError, fault: HealthReason) {
+        self.motor_link = MotorLink::Down;
+        self.last_error = Some((e.to_string(), wall_clock_ms()));
+        self.connect_error = Some(e);
+        self.motor_faults = vec![fault];
+        self.reconnect = Some(Reconnect {
+            attempts: self.reconnect.map_or(0, |r| r.attempts) + 1,
+            next_attempt: Instant::now() + self.config.reconnect_interval(),
+        });
+    }
+
+    /// Retry opening the motor bus once the reconnect interval has passed
+    fn maintain_motor_link(&mut self) {
+        if self.motor_link == MotorLink::Down
+            && let Some(r) = self.reconnect
+            && Instant::now() >= r.next_attempt
+        {
+            info!("Reconnecting to motor bus (attempt {})", r.attempts + 1);
+            self.connect_motors();
+        }
+    }
+
+    /// Apply the reports of motor I/O finished since the last call
+    fn poll_motors(&mut self) {
+        while let Some(report) = self.motor_io.as_ref().and_then(MotorThread::try_recv) {
+            self.on_motor_report(report);
+        }
+    }
+
+    /// Wait until the motor thread has worked through every queued request
+    ///
+    /// Applies the reports on the way; returns `false` after `timeout`.
+    fn sync_motors(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut flush = Some(Request::Flush);
+        while let Some(io) = &self.motor_io {
+            if let Some(request) = flush.take() {
+                flush = io.send(request).err();
+            }
+            let wait = deadline.saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                return false;
+            }
+            // Poll while the queue is full, so the flush gets in eventually
+            let wait = if flush.is_some() {
+                wait.min(Duration::from_millis(1))
+            } else {
+                wait
+            };
+            match io.recv_timeout(wait) {
+                Some(Report::Flushed) => return true,
+                Some(report) => self.on_motor_report(report),
+                None if flush.is_none() => return false,
+                None => {}
+            }
+        }
+        true
+    }
+
+    fn on_motor_report(&mut self, report: Report) {
+        self.motor_reported_at = Instant::now();
+        match report {
+            Report::Connected(Ok(())) => {
+                self.motor_link = MotorLink::Ready;
+                self.motor_faults.clear();
+                self.connect_error = None;
+                if let Some(r) = self.reconnect.take() {
+                    info!("Motor bus recovered after {} failed attempts", r.attempts);
+                }
+                info!("Motor driver initialized successfully");
+            }
+            Report::Connected(Err(e)) => {
+                let fault = HealthReason::from(&e);
+                self.on_connect_failed(e, fault);
+            }
+            Report::Cycle(result) => {
+                // Every cycle is a new round of motor I/O: earlier faults are cleared
+                self.motor_faults.clear();
+                match result {
+                    Ok(readings) => {
+                        self.check_feedback(&readings.wheels);
+                        self.readings = Some(readings);
+                    }
+                    Err(e) => {
+                        error!("Motor I/O failed: {}", e);
+                        self.on_motor_error(&e);
+                    }
+                }
+            }
+            Report::TorqueEnabled(Ok(())) => {
+                if self.estop.is_some_and(|e| e.resetting) {
+                    info!("Emergency stop reset");
+                    self.estop = None;
+                }
+            }
+            Report::TorqueEnabled(Err(e)) => {
+                error!("Failed to re-enable torque, estop stays latched: {}", e);
+                if let Some(estop) = self.estop.as_mut() {
+                    estop.resetting = false;
+                }
+                self.on_motor_error(&e);
+            }
+            Report::Scanned(result) => {
+                self.scan_pending = false;
+                self.scan_result = Some(result.map_err(|e| {
+                    error!("Motor bus scan failed: {}", e);
+                    self.on_motor_error(&e);
+                    e.to_string()
+                }));
+            }
+            Report::Flushed => {}
+        }
+    }
+
+    /// Record a failed motor operation
+    ///
+    /// A lost bus drops the driver and starts the reconnect cycle.
+    fn on_motor_error(&mut self, e: &FeetechError) {
+        self.last_error = Some((e.to_string(), wall_clock_ms()));
+        let fault = HealthReason::from(e);
+        let lost = matches!(fault, HealthReason::BusDisconnected { .. });
+        if !self.motor_faults.contains(&fault) {
+            self.motor_faults.push(fault);
+        }
+
+        if lost {
+            warn!(
+                "Motor bus lost: {}. Retrying every {:?}",
+                e,
+                self.config.reconnect_interval()
+            );
+            self.motor_link = MotorLink::Down;
+            self.arm_goal = None;
+            self.limiter.reset();
+            self.reconnect = Some(Reconnect {
+                attempts: 0,
+                next_attempt: Instant::now() + self.config.reconnect_interval(),
+            });
+        }
+    }
+
+    /// Engage or reset the latching emergency stop
+    fn on_estop(&mut self, cmd: EstopCommand) {
+        match cmd {
+            EstopCommand::Engage {
+                disable_torque,
+                release_arm,
+            } => {
+                if self.estop.is_none() {
+                    warn!("Emergency stop engaged");
+                }
+                let estop = self.estop.get_or_insert(Estop {
+                    torque_disabled: false,
+                    arm_released: false,
+                    resetting: false,
+                });
+                estop.torque_disabled |= disable_torque;
+                estop.arm_released |= disable_torque && release_arm;
+                estop.resetting = false;
+
+                // Zero immediately instead of waiting for the next tick;
+                // the arm holds where it is unless released
+                self.mux.clear();
+                self.arm_goal = None;
+                self.limiter.reset();
+                self.send_to_motors(&BaseActuation::default());
+            }
+            EstopCommand::Reset => {
+                let Some(estop) = self.estop else {
+                    return;
+                };
+                if estop.torque_disabled && self.motor_link == MotorLink::Ready {
+                    // Re-initializing zeroes the goals before enabling torque;
+                    // the estop stays latched until the motor thread confirms
+                    if estop.resetting {
+                        return;
+                    }
+                    let io = self.motor_io.as_ref().expect("ready motors have a thread");
+                    match io.send(Request::EnableTorque) {
+                        Ok(()) => {
+                            self.estop = Some(Estop {
+                                resetting: true,
+                                ..estop
+                            })
+                        }
+                        Err(_) => warn!("Motor thread busy, estop stays latched; reset again"),
+                    }
+                    return;
+                }
+                info!("Emergency stop reset");
+                self.estop = None;
+            }
+        }
+    }
+
+    /// Answer a parameter request, staging changes for the next tick
+    ///
+    /// Replies with the requested parameters; a set replies with the new value.
+    fn on_param_request(&mut self, request: ParamRequest) -> Result<Vec<Param>, ParamError> {
+        let current = self
+            .staged_params
+            .as_ref()
+            .map_or(&self.config, |(config, _)| config);
+        let param = |name: &str, config: &RuntimeConfig| -> Result<Param, ParamError> {
+            Ok(Param {
+                name: name.to_string(),
+                value: params::get(config, name)?,
+            })
+        };
+
+        match request {
+            ParamRequest::List => params::names().map(|n| param(n, current)).collect(),
+            ParamRequest::Get { name } => Ok(vec![param(&name, current)?]),
+            ParamRequest::Set { name, value } => {
+                let updated = params::set(current, &self.startup_config, &name, value)?;
+                let reply = param(&name, &updated)?;
+                info!(
+                    "Parameter {} set to {} (applied next tick)",
+                    name, reply.value
+                );
+                let (staged, names) = self
+                    .staged_params
+                    .get_or_insert_with(|| (self.config.clone(), Vec::new()));
+                *staged = updated;
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                Ok(vec![reply])
+            }
+        }
+    }
+
+    /// Apply parameter changes staged since the last tick
+    fn apply_params(&mut self) -> Vec<ParamChange> {
+        let Some((updated, names)) = self.staged_params.take() else {
+            return Vec::new();
+        };
+        let changes: Vec<ParamChange> = names
+            .into_iter()
+            .filter_map(|name| {
+                let old = params::get(&self.config, &name).ok()?;
+                let new = params::get(&updated, &name).ok()?;
+                (old != new).then_some(ParamChange { name, old, new })
+            })
+            .collect();
+        for change in &changes {
+            info!(
+                "Parameter {} changed: {} -> {}",
+                change.name, change.old, change.new
+            );
+        }
+
+        self.config = updated;
+        // The `default` source follows `cmd_timeout_ms`
+        if let Some(default) = self.mux.source_index(DEFAULT_SOURCE) {
+            self.mux.set_timeout(default, self.config.cmd_timeout_ms);
+        }
+        changes
+    }
+
+    /// Process incoming command from a mux source
+    fn on_command(&mut self, source: usize, cmd: BaseCommand, now: Instant) {
+        if self.estop.is_some() {
+            debug!("Ignoring command while estop is latched: {:?}", &cmd);
+            return;
+        }
+        let name = self.mux.source_name(source).to_string();
+        debug!("Received command from {}: {:?}", name, &cmd);
+
+        if let Err(reason) = self.mux.on_command(source, cmd, now, wall_clock_ms()) {
+            self.dropped_cmds += 1;
+            if self
+                .dropped_warned_at
+                .is_none_or(|at| now.duration_since(at) >= DROP_WARN_INTERVAL)
+            {
+                warn!(
+                    "Dropped command from {}: {} ({} dropped since the last warning)",
+                    name, reason, self.dropped_cmds
+                );
+                self.dropped_cmds = 0;
+                self.dropped_warned_at = Some(now);
+            }
+        }
     }
 
-    /// Process incoming command
-    fn on_command(&mut self, cmd: BaseCommand) {
-        debug!("Received command: {:?}", &cmd);
-        self.latest_cmd = Some(cmd);
-        self.cmd_received_at = Instant::now();
+    /// Keep the latest arm command, sent with the next setpoint
+    fn on_arm_command(&mut self, cmd: ArmCommand) {
+        if self.estop.is_some() || self.motor_link != MotorLink::Ready {
+            debug!("Ignoring arm command, estop latched or motors offline");
+            return;
+        }
+        if !cmd.positions.to_array().iter().all(|deg| deg.is_finite()) {
+            warn!("Ignoring arm command with non-finite joints: {:?}", cmd);
+            return;
+        }
+        debug!("Received arm command: {:?}", &cmd);
+        self.arm_goal = Some(cmd.positions);
     }
 
-    /// Compute actuation based on watchdog state
+    /// Compute actuation: watchdog-gated target, then acceleration limits
     fn compute_actuation(&mut self) -> BaseActuation {
-        let cmd_age = self.cmd_received_at.elapsed();
-
-        if cmd_age > CMD_TIMEOUT {
-            // trigger watchdog if command is stale
-            if self.health != RuntimeHealth::CmdStale {
-                warn!("Command stale ({:?} old), stopping robot", cmd_age);
-            }
-            self.health = RuntimeHealth::CmdStale;
-            BaseActuation::default() // Zero velocity
-        } else if let Some(ref cmd) = self.latest_cmd {
-            self.health = RuntimeHealth::Ok;
-            BaseActuation::from(cmd)
-        } else {
-            // No command ever received
-            self.health = RuntimeHealth::CmdStale;
-            BaseActuation::default()
+        let now = Instant::now();
+        let target = self.compute_target(now);
+
+        // Measured tick interval, bounded so a stalled loop cannot cause a jump
+        let dt = now
+            .duration_since(self.actuated_at)
+            .min(self.config.loop_period() * 4)
+            .as_secs_f32();
+        self.actuated_at = now;
+
+        if self.estop.is_some() || self.reconnect.is_some() {
+            // Estop latched or motors offline: hold zero, restart from standstill
+            self.limiter.reset();
+            return BaseActuation::default();
         }
+
+        // A watchdog trip brakes with the emergency deceleration limits
+        let emergency = self.cmd_stale;
+        self.limiter
+            .apply(&target, &self.config.accel, emergency, dt)
     }
 
-    /// Send actuation to motors
+    /// Compute the target velocity from the highest-priority fresh source
+    ///
+    /// Every source has its own timeout; when none is fresh at `now` the watchdog trips.
+    fn compute_target(&mut self, now: Instant) -> BaseActuation {
+        let selected = self
+            .mux
+            .select(now)
+            .map(|(source, cmd)| (source, BaseActuation::from(cmd)));
+        let active = selected.as_ref().map(|&(source, _)| source);
+        if active != self.active_source
+            && let Some(source) = active
+        {
+            info!("Active command source: {}", self.mux.source_name(source));
+        }
+        self.active_source = active;
+
+        match selected {
+            Some((_, mut actuation)) => {
+                self.cmd_stale = false;
+                let limits = &self.config.limits;
+                actuation.x_vel = actuation.x_vel.clamp(-limits.max_x_vel, limits.max_x_vel);
+                actuation.y_vel = actuation.y_vel.clamp(-limits.max_y_vel, limits.max_y_vel);
+                actuation.theta_vel = actuation
+                    .theta_vel
+                    .clamp(-limits.max_theta_vel, limits.max_theta_vel);
+                actuation
+            }
+            None => {
+                // trigger watchdog if no source has a fresh command
+                if !self.cmd_stale {
+                    warn!("No fresh command from any source, stopping robot");
+                }
+                self.cmd_stale = true;
+                BaseActuation::default() // Zero velocity
+            }
+        }
+    }
+
+    /// Hand a setpoint to the motor thread
+    ///
+    /// Never waits: if the thread is still busy with earlier work, the setpoint
+    /// replaces the one it has not picked up yet, so a stop is never lost.
     fn send_to_motors(&mut self, actuation: &BaseActuation) {
-        if let Some(ref mut driver) = self.motor_driver {
-            if let Err(e) =
-                driver.set_body_velocity(actuation.x_vel, actuation.y_vel, actuation.theta_vel)
-            {
-                error!("Failed to send motor command: {}", e);
+        if self.motor_link != MotorLink::Ready {
+            return;
+        }
+        let Some(io) = &self.motor_io else {
+            return;
+        };
+        let setpoint = Setpoint {
+            base: actuation.clone(),
+            kinematics: self.config.kinematics,
+            arm: self.arm_goal,
+            torque: !self
+                .estop
+                .is_some_and(|e| e.torque_disabled && !e.resetting),
+            arm_torque: !self.estop.is_some_and(|e| e.arm_released && !e.resetting),
+        };
+        io.send_setpoint(setpoint);
+        self.sent_base = actuation.clone();
+    }
+
+    /// Motor readings received since the last call
+    fn take_readings(&mut self) -> Option<Readings> {
+        self.readings.take()
+    }
+
+    /// Present state of the arm joints, if there is an arm
+    fn arm_state(readings: &Readings) -> Option<ArmState> {
+        readings.arm.map(|joints| ArmState {
+            position: ArmJoints::from_array(joints.map(|j| j.position)),
+            velocity: ArmJoints::from_array(joints.map(|j| j.velocity)),
+            load: ArmJoints::from_array(joints.map(|j| j.load)),
+        })
+    }
+
+    /// Record temperature and voltage readings outside the configured limits
+    fn check_feedback(&mut self, feedback: &[MotorFeedback; 3]) {
+        let limits = self.config.health;
+        for fb in feedback {
+            if fb.temperature > limits.max_temperature {
+                self.motor_faults.push(HealthReason::OverTemperature {
+                    id: fb.id,
+                    temperature: fb.temperature,
+                    limit: limits.max_temperature,
+                });
+            }
+            if fb.voltage < limits.min_voltage {
+                self.motor_faults.push(HealthReason::LowVoltage {
+                    id: fb.id,
+                    voltage: fb.voltage,
+                    limit: limits.min_voltage,
+                });
             }
         }
     }
 
-    /// Stop motors safely
-    fn stop_motors(&mut self) {
-        if let Some(ref mut driver) = self.motor_driver {
-            if let Err(e) = driver.stop() {
-                error!("Failed to stop motors: {}", e);
+    /// Current health: watchdog state plus faults from the latest motor I/O
+    fn health(&self) -> RuntimeHealth {
+        let mut reasons = Vec::new();
+        if let Some(estop) = self.estop {
+            reasons.push(HealthReason::EstopLatched {
+                torque_disabled: estop.torque_disabled,
+            });
+        }
+        if self.cmd_stale {
+            reasons.push(HealthReason::CmdStale);
+        }
+        reasons.extend(self.motor_faults.iter().cloned());
+        let silent = self.motor_reported_at.elapsed();
+        if self.motor_link == MotorLink::Ready && silent > self.config.loop_period() * STALL_TICKS {
+            reasons.push(HealthReason::MotorIoStalled {
+                stalled_ms: silent.as_millis() as u64,
+            });
+        }
+        if let Some(r) = self.reconnect {
+            reasons.push(HealthReason::MotorReconnecting {
+                attempts: r.attempts,
+                retry_in_ms: r
+                    .next_attempt
+                    .saturating_duration_since(Instant::now())
+                    .as_millis() as u64,
+            });
+        }
+        let mut health = RuntimeHealth::from_reasons(reasons);
+        health.active_source = self
+            .active_source
+            .map(|source| self.mux.source_name(source).to_string());
+        health
+    }
+
+    /// Effective config, build info, uptime and motor state for the info queryable
+    fn info(&self) -> RuntimeInfo {
+        let motors = if !self.config.motor.enabled && self.sim.is_none() {
+            MotorState::Disabled
+        } else if let Some(r) = self.reconnect {
+            MotorState::Offline {
+                attempts: r.attempts,
             }
+        } else if self.motor_link == MotorLink::Ready {
+            MotorState::Ready
+        } else {
+            MotorState::Uninitialized
+        };
+        let now_ms = wall_clock_ms();
+        RuntimeInfo {
+            build: BuildInfo::current(),
+            config: serde_json::to_value(&self.config).unwrap_or_default(),
+            uptime_ms: self.started_at.elapsed().as_millis() as u64,
+            motors,
+            last_error: self.last_error.as_ref().map(|(message, at_ms)| LastError {
+                message: message.clone(),
+                at_ms: *at_ms,
+                ago_ms: now_ms.saturating_sub(*at_ms),
+            }),
+        }
+    }
+
+    /// Start a sweep of the motor bus for the scan queryable
+    ///
+    /// Only while the estop is latched and the base was last sent a zero
+    /// setpoint, since the wheels get no setpoints during the sweep. The motor
+    /// thread sweeps while the control loop keeps ticking; the inventory shows
+    /// up in `take_scan_result`.
+    fn request_scan(&mut self, request: ScanRequest) -> Result<(), String> {
+        let baud_rates = request.baud_rates.unwrap_or_else(|| BAUD_RATES.to_vec());
+        if let Some(rate) = baud_rates.iter().find(|&&b| baud_rate_index(b).is_none()) {
+            return Err(format!(
+                "Unsupported baud rate {}: expected one of {:?}",
+                rate, BAUD_RATES
+            ));
+        }
+        if self.scan_pending {
+            return Err("A scan is already running".to_string());
+        }
+        let (MotorLink::Ready, Some(io)) = (self.motor_link, &self.motor_io) else {
+            return Err("Motor bus is not connected".to_string());
+        };
+        if self.estop.is_none() {
+            return Err("Engage the estop before scanning the motor bus".to_string());
+        }
+        if self.sent_base != BaseActuation::default() {
+            return Err("The base is still moving, try again".to_string());
+        }
+
+        io.send(Request::Scan(baud_rates.clone()))
+            .map_err(|_| "Motor thread busy, try again".to_string())?;
+        warn!(
+            "Scanning the motor bus at {:?} baud, motors paused",
+            baud_rates
+        );
+        self.scan_pending = true;
+        Ok(())
+    }
+
+    /// Outcome of the last scan, once the motor thread finished it
+    fn take_scan_result(&mut self) -> Option<Result<BusInventory, String>> {
+        self.scan_result.take()
+    }
+
+    /// Advance the simulated base to now and return its ground-truth pose,
+    /// if running in --sim mode
+    fn sim_pose(&mut self) -> Option<BaseOdometry> {
+        let kinematics = self.config.kinematics;
+        self.sim.as_mut().map(|sim| sim.update(&kinematics))
+    }
+
+    /// Integrate measured wheel velocities, read at `read_at`, into the odometry pose
+    fn update_odometry(&mut self, feedback: &[MotorFeedback; 3], read_at: Instant) -> BaseOdometry {
+        let dt = self
+            .odom_updated_at
+            .map_or(0.0, |t| read_at.duration_since(t).as_secs_f32());
+        self.odom_updated_at = Some(read_at);
+
+        let [left, back, right] = feedback.map(|f| f.velocity);
+        let wheels = WheelVelocities::new(left, back, right);
+        let k = &self.config.kinematics;
+        let twist = wheel_raw_to_body_with_params(wheels, k.wheel_radius, k.base_radius);
+        self.odometry.update(twist, dt);
+        self.odometry.message()
+    }
+
+    /// Combine measured feedback with the last commanded wheel velocities
+    fn wheel_state(readings: &Readings) -> WheelState {
+        let commanded = readings.commanded.as_array();
+
+        let [left, back, right] = [0, 1, 2].map(|i| {
+            let fb = &readings.wheels[i];
+            WheelFeedback {
+                id: fb.id,
+                commanded_vel: commanded[i],
+                measured_vel: fb.velocity,
+                position: fb.position,
+                load: fb.load as f32 / 10.0,
+                voltage: fb.voltage,
+                temperature: fb.temperature,
+            }
+        });
+        WheelState { left, back, right }
+    }
+
+    /// Stop the base, waiting briefly for the motor thread to apply it
+    fn stop_motors(&mut self) {
+        self.send_to_motors(&BaseActuation::default());
+        if !self.sync_motors(STOP_TIMEOUT) {
+            warn!("Motor thread did not confirm the stop");
         }
     }
 }
 
 pub async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let args = Args::parse();
+    let runtime_config = args.load_config()?;
 
     let config = if args.listen {
         // Network mode: listen on TCP
@@ -125,37 +927,122 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     info!("Opening Zenoh session...");
     let session = zenoh::open(config).await?;
-    let subscriber = session.declare_subscriber(TOPIC_CMD_BASE).await?;
-    let pub_actuation = session.declare_publisher(TOPIC_RT_BASE).await?;
-    let pub_health = session.declare_publisher(TOPIC_HEALTH).await?;
+    let topics = runtime_config.topics.clone();
+    let sources = runtime_config.command_sources();
+    let mut cmd_subs = Vec::with_capacity(sources.len());
+    for source in &sources {
+        let topic = runtime_config.source_topic(source);
+        cmd_subs.push(session.declare_subscriber(topic).await?);
+    }
+    let estop_sub = session.declare_subscriber(&topics.estop).await?;
+    let arm_sub = session.declare_subscriber(&topics.cmd_arm).await?;
+    let info_queryable = session.declare_queryable(&topics.info).await?;
+    let params_queryable = session.declare_queryable(&topics.params).await?;
+    let scan_queryable = session.declare_queryable(&topics.scan).await?;
+    let EncodingConfig { telemetry, health } = runtime_config.encoding;
+    let pub_actuation = session
+        .declare_publisher(&topics.rt_base)
+        .encoding(telemetry.zenoh_encoding())
+        .await?;
+    let pub_health = session
+        .declare_publisher(&topics.health)
+        .encoding(health.zenoh_encoding())
+        .await?;
+    let pub_odom = session
+        .declare_publisher(&topics.odom)
+        .encoding(telemetry.zenoh_encoding())
+        .await?;
+    let pub_wheels = session
+        .declare_publisher(&topics.wheels)
+        .encoding(telemetry.zenoh_encoding())
+        .await?;
+    let pub_arm_state = session
+        .declare_publisher(&topics.arm_state)
+        .encoding(telemetry.zenoh_encoding())
+        .await?;
+    let pub_param_changes = session
+        .declare_publisher(&topics.param_changes)
+        .encoding(health.zenoh_encoding())
+        .await?;
+    let pub_sim_pose = if runtime_config.sim.enabled {
+        let publisher = session
+            .declare_publisher(&topics.sim_pose)
+            .encoding(telemetry.zenoh_encoding())
+            .await?;
+        Some(publisher)
+    } else {
+        None
+    };
+
+    let mut recorder = match &args.record {
+        Some(path) => {
+            info!("Recording to {}", path.display());
+            Some(Recorder::create(path)?)
+        }
+        None => None,
+    };
 
-    let mut runtime = Runtime::new();
+    let calibration = match &runtime_config.motor.calibration {
+        Some(path) => {
+            info!("Loading motor calibration from {}", path.display());
+            Calibration::load(path)?
+        }
+        None => Calibration::default(),
+    };
+
+    let mut runtime = Runtime::new(runtime_config);
+    runtime.calibration = calibration;
 
     if let Err(e) = runtime.init_motors() {
         warn!(
-            "Failed to initialize motors: {}. Running without motor control.",
-            e
+            "Failed to initialize motors: {}. Retrying every {:?}.",
+            e,
+            runtime.config.reconnect_interval()
         );
     }
 
-    let mut tick = interval(Duration::from_millis(1000 / LOOP_HZ));
+    let mut tick = interval(runtime.config.loop_period());
 
     info!(
         "Runtime started: {}Hz loop, {}ms watchdog timeout",
-        LOOP_HZ,
-        CMD_TIMEOUT.as_millis()
+        runtime.config.loop_hz, runtime.config.cmd_timeout_ms
+    );
+    for source in &sources {
+        info!(
+            "Command source {} (priority {}, {}ms timeout): {}",
+            source.name,
+            source.priority,
+            source.timeout_ms,
+            runtime.config.source_topic(source)
+        );
+    }
+    info!("Subscribed to: {}, {}", topics.estop, topics.cmd_arm);
+    info!("Serving runtime info on: {}", topics.info);
+    info!("Serving live parameters on: {}", topics.params);
+    info!("Serving motor bus scans on: {}", topics.scan);
+    info!(
+        "Publishing to: {}, {}, {}, {}, {}, {}",
+        topics.rt_base,
+        topics.health,
+        topics.odom,
+        topics.wheels,
+        topics.arm_state,
+        topics.param_changes
     );
-    info!("Subscribed to: {}", TOPIC_CMD_BASE);
-    info!("Publishing to: {}, {}", TOPIC_RT_BASE, TOPIC_HEALTH);
     info!(
         "Motor control: {}",
-        if runtime.motor_driver.is_some() {
+        if runtime.sim.is_some() {
+            "SIMULATED"
+        } else if runtime.motor_link == MotorLink::Ready {
             "ENABLED"
         } else {
             "DISABLED"
         }
     );
 
+    // Scan queries waiting for the motor thread
+    let mut pending_scans = Vec::new();
+
     // Setup graceful shutdown
     let shutdown = tokio::signal::ctrl_c();
     tokio::pin!(shutdown);
@@ -163,32 +1050,123 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     loop {
         tokio::select! {
             _ = tick.tick() => {
-                // 1. Drain all pending commands (non-blocking), keep latest
-                while let Ok(Some(sample)) = subscriber.try_recv() {
-                    let payload = sample.payload().to_bytes();
-                    match serde_json::from_slice::<BaseCommand>(&payload) {
-                        Ok(cmd) => {
-                            runtime.on_command(cmd);
+                // 1. Apply parameter changes staged since the last tick
+                for change in runtime.apply_params() {
+                    pub_param_changes.put(health.encode(&change)?).await?;
+                }
+
+                // 2. Drain all pending commands (non-blocking), keep latest per source
+                for (source, subscriber) in cmd_subs.iter().enumerate() {
+                    while let Ok(Some(sample)) = subscriber.try_recv() {
+                        match codec::decode_sample::<BaseCommand>(&sample) {
+                            Ok(cmd) => {
+                                let topic = sample.key_expr().as_str();
+                                record(&mut recorder, topic, Entry::Command(cmd.clone()));
+                                runtime.on_command(source, cmd, Instant::now());
+                            }
+                            Err(e) => {
+                                warn!("Failed to parse command: {}", e);
+                            }
                         }
-                        Err(e) => {
-                            warn!("Failed to parse command: {}", e);
+                    }
+                }
+
+                // Only the latest arm command matters
+                while let Ok(Some(sample)) = arm_sub.try_recv() {
+                    match codec::decode_sample::<ArmCommand>(&sample) {
+                        Ok(cmd) => {
+                            record(&mut recorder, &topics.cmd_arm, Entry::Arm(cmd));
+                            runtime.on_arm_command(cmd);
                         }
+                        Err(e) => warn!("Failed to parse arm command: {}", e),
                     }
                 }
 
-                // 2. Compute actuation (includes watchdog logic)
+                // 3. Collect finished motor I/O, bring the motor bus back if it was lost
+                runtime.poll_motors();
+                runtime.maintain_motor_link();
+                if let Some(result) = runtime.take_scan_result() {
+                    for query in pending_scans.drain(..) {
+                        reply(&query, "scan", result.as_ref().map_err(String::as_str)).await;
+                    }
+                }
+
+                // 4. Compute actuation (includes watchdog logic)
                 let actuation = runtime.compute_actuation();
 
-                // 3. Send to motors
+                // 5. Hand the setpoint (base and latest arm goal) to the motor thread
                 runtime.send_to_motors(&actuation);
 
-                // 4. Publish actuation over Zenoh
-                let actuation_json = serde_json::to_string(&actuation)?;
-                pub_actuation.put(actuation_json).await?;
+                // 6. Publish actuation over Zenoh
+                pub_actuation.put(telemetry.encode(&actuation)?).await?;
+                record(&mut recorder, &topics.rt_base, Entry::Actuation(actuation));
+
+                // 7. Publish wheel telemetry, odometry and arm state (only when the
+                //    motor thread reported new readings), and the ground-truth pose in
+                //    --sim mode
+                if let Some(readings) = runtime.take_readings() {
+                    let wheels = Runtime::wheel_state(&readings);
+                    pub_wheels.put(telemetry.encode(&wheels)?).await?;
+                    record(&mut recorder, &topics.wheels, Entry::Wheels(wheels));
 
-                // 5. Publish health
-                let health_json = serde_json::to_string(&runtime.health)?;
-                pub_health.put(health_json).await?;
+                    let odom = runtime.update_odometry(&readings.wheels, readings.read_at);
+                    pub_odom.put(telemetry.encode(&odom)?).await?;
+
+                    if let Some(arm) = Runtime::arm_state(&readings) {
+                        pub_arm_state.put(telemetry.encode(&arm)?).await?;
+                        record(&mut recorder, &topics.arm_state, Entry::ArmState(arm));
+                    }
+                }
+                if let Some(pose) = runtime.sim_pose()
+                    && let Some(publisher) = &pub_sim_pose
+                {
+                    publisher.put(telemetry.encode(&pose)?).await?;
+                }
+
+                // 8. Publish health (faults as of the latest motor report)
+                let runtime_health = runtime.health();
+                pub_health.put(health.encode(&runtime_health)?).await?;
+                record(&mut recorder, &topics.health, Entry::Health(runtime_health));
+            }
+            // Emergency stops are handled as soon as they arrive, not on the next tick
+            Ok(sample) = estop_sub.recv_async() => {
+                match codec::decode_sample::<EstopCommand>(&sample) {
+                    Ok(cmd) => {
+                        record(&mut recorder, &topics.estop, Entry::Estop(cmd));
+                        runtime.on_estop(cmd);
+                    }
+                    Err(e) => warn!("Failed to parse estop command: {}", e),
+                }
+            }
+            Ok(query) = info_queryable.recv_async() => {
+                reply(&query, "info", Ok(&runtime.info())).await;
+            }
+            // Changes are validated now and applied at the start of the next tick
+            Ok(query) = params_queryable.recv_async() => {
+                let result = codec::decode_query::<ParamRequest>(&query)
+                    .map_err(|e| e.to_string())
+                    .and_then(|request| {
+                        runtime
+                            .on_param_request(request.unwrap_or(ParamRequest::List))
+                            .map_err(|e| e.to_string())
+                    });
+                if let Err(e) = &result {
+                    warn!("Rejected parameter request: {}", e);
+                }
+                reply(&query, "parameter", result.as_ref().map_err(String::as_str)).await;
+            }
+            // The motor thread sweeps; the reply goes out on the tick that sees the result
+            Ok(query) = scan_queryable.recv_async() => {
+                let result = codec::decode_query::<ScanRequest>(&query)
+                    .map_err(|e| e.to_string())
+                    .and_then(|request| runtime.request_scan(request.unwrap_or_default()));
+                match result {
+                    Ok(()) => pending_scans.push(query),
+                    Err(e) => {
+                        warn!("Rejected scan request: {}", e);
+                        reply::<BusInventory>(&query, "scan", Err(&e)).await;
+                    }
+                }
             }
             _ = &mut shutdown => {
                 info!("Shutdown signal received");
@@ -200,7 +1178,481 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Graceful shutdown: stop motors
     info!("Stopping motors...");
     runtime.stop_motors();
+    if let Some(r) = recorder
+        && let Err(e) = r.finish()
+    {
+        error!("Recording failed: {}", e);
+    }
     info!("Runtime shutdown complete");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::HealthLevel;
+    use crate::motor::feetech::{FeetechBus, Register};
+    use crate::motor::sim::SimServoBank;
+
+    /// Runtime on a simulated LeKiwi chain (servos 1-9) driving motors `ids`
+    fn sim_runtime(ids: [u8; 3]) -> Runtime {
+        let mut config = RuntimeConfig::default();
+        config.sim.enabled = true;
+        config.arm.enabled = true; // the simulated bus carries the arm too
+        let mut runtime = Runtime::new(config);
+        runtime.config.motor.ids = ids;
+        runtime
+    }
+
+    /// Runtime driving an already initialized driver on `bank`
+    fn runtime_on(bank: &SimServoBank) -> Runtime {
+        let mut driver =
+            MotorDriver::with_bus(FeetechBus::new(Box::new(bank.transport())), [7, 8, 9]);
+        driver.initialize().unwrap();
+        let mut runtime = sim_runtime([7, 8, 9]);
+        runtime.connect(Box::new(move || Ok(driver)));
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        runtime
+    }
+
+    /// Run one round of motor I/O and wait for it
+    fn cycle(runtime: &mut Runtime) {
+        let actuation = runtime.compute_actuation();
+        runtime.send_to_motors(&actuation);
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+    }
+
+    #[test]
+    fn test_health_starts_stale() {
+        let runtime = sim_runtime([7, 8, 9]);
+        let health = runtime.health();
+        assert_eq!(health.level, HealthLevel::Warn);
+        assert_eq!(health.reasons, vec![HealthReason::CmdStale]);
+    }
+
+    #[test]
+    fn test_health_reports_missing_motor() {
+        let mut runtime = sim_runtime([7, 8, 10]);
+        assert!(runtime.init_motors().is_err());
+        let health = runtime.health();
+        assert_eq!(health.level, HealthLevel::Error);
+        assert!(
+            health
+                .reasons
+                .contains(&HealthReason::MotorNotResponding { id: 10 })
+        );
+    }
+
+    #[test]
+    fn test_health_reports_temperature_and_voltage() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        runtime.config.health.max_temperature = 25; // simulated servos sit at 30 °C
+        runtime.config.health.min_voltage = 12.5; // and at 12.0 V
+        runtime.init_motors().unwrap();
+
+        runtime.on_command(
+            0,
+            BaseCommand {
+                x_vel: 0.0,
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        cycle(&mut runtime);
+        assert!(runtime.take_readings().is_some());
+
+        let health = runtime.health();
+        assert_eq!(health.level, HealthLevel::Error);
+        assert_eq!(health.reasons.len(), 6);
+        assert!(health.reasons.contains(&HealthReason::OverTemperature {
+            id: 8,
+            temperature: 30,
+            limit: 25
+        }));
+        assert!(health.reasons.contains(&HealthReason::LowVoltage {
+            id: 9,
+            voltage: 12.0,
+            limit: 12.5
+        }));
+    }
+
+    #[test]
+    fn test_reconnects_after_failed_startup() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        let bank = runtime.sim.as_ref().unwrap().bank().clone();
+        bank.set_connected(false);
+        assert!(runtime.init_motors().is_err());
+
+        let health = runtime.health();
+        assert_eq!(health.level, HealthLevel::Error);
+        assert!(matches!(
+            health.reasons[1],
+            HealthReason::BusDisconnected { .. }
+        ));
+        assert!(matches!(
+            health.reasons[2],
+            HealthReason::MotorReconnecting { attempts: 1, .. }
+        ));
+
+        // Not yet due: nothing happens
+        bank.set_connected(true);
+        runtime.maintain_motor_link();
+        assert_eq!(runtime.motor_link, MotorLink::Down);
+
+        runtime.reconnect.as_mut().unwrap().next_attempt = Instant::now();
+        runtime.maintain_motor_link();
+        assert_eq!(runtime.motor_link, MotorLink::Connecting);
+        runtime.sync_motors(INIT_TIMEOUT);
+        assert_eq!(runtime.motor_link, MotorLink::Ready);
+        assert_eq!(runtime.health().reasons, vec![HealthReason::CmdStale]);
+    }
+
+    #[test]
+    fn test_lost_bus_stops_and_reconnects() {
+        let bank = SimServoBank::new(&[7, 8, 9]);
+        let mut runtime = runtime_on(&bank);
+
+        runtime.on_command(
+            0,
+            BaseCommand {
+                x_vel: 0.2,
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        bank.set_connected(false);
+        cycle(&mut runtime);
+
+        assert_eq!(runtime.motor_link, MotorLink::Down);
+        let health = runtime.health();
+        assert!(matches!(
+            health.reasons[..],
+            [
+                HealthReason::BusDisconnected { .. },
+                HealthReason::MotorReconnecting { attempts: 0, .. }
+            ]
+        ));
+        assert_eq!(runtime.compute_actuation().x_vel, 0.0);
+
+        // Reconnect opens a fresh (simulated) bus
+        runtime.reconnect.as_mut().unwrap().next_attempt = Instant::now();
+        runtime.maintain_motor_link();
+        runtime.sync_motors(INIT_TIMEOUT);
+        assert_eq!(runtime.motor_link, MotorLink::Ready);
+        assert!(runtime.health().is_ok());
+    }
+
+    #[test]
+    fn test_estop_latches_until_reset() {
+        let bank = SimServoBank::new(&[7, 8, 9]);
+        let mut runtime = runtime_on(&bank);
+
+        let forward = BaseCommand {
+            x_vel: 0.2,
+            ..Default::default()
+        };
+        runtime.on_command(0, forward.clone(), Instant::now());
+        runtime.compute_actuation();
+        runtime.on_estop(EstopCommand::Engage {
+            disable_torque: true,
+            release_arm: false,
+        });
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        assert_eq!(bank.peek(8, Register::TorqueEnable), Some(0));
+        assert_eq!(bank.present_velocity(8), Some(0));
+
+        // Commands are ignored while latched
+        runtime.on_command(0, forward.clone(), Instant::now());
+        assert_eq!(runtime.compute_actuation().x_vel, 0.0);
+        let health = runtime.health();
+        assert_eq!(health.level, HealthLevel::Error);
+        assert_eq!(
+            health.reasons[0],
+            HealthReason::EstopLatched {
+                torque_disabled: true
+            }
+        );
+
+        // Latched until the motor thread has re-enabled torque
+        runtime.on_estop(EstopCommand::Reset);
+        assert!(runtime.estop.is_some());
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        assert_eq!(bank.peek(8, Register::TorqueEnable), Some(1));
+        assert!(
+            !runtime
+                .health()
+                .reasons
+                .iter()
+                .any(|r| matches!(r, HealthReason::EstopLatched { .. }))
+        );
+        runtime.on_command(0, forward, Instant::now());
+        assert!(runtime.compute_actuation().x_vel > 0.0);
+    }
+
+    #[test]
+    fn test_estop_message_format() {
+        let engage: EstopCommand =
+            serde_json::from_str(r#"{"action": "engage", "disable_torque": true}"#).unwrap();
+        assert_eq!(
+            engage,
+            EstopCommand::Engage {
+                disable_torque: true,
+                release_arm: false,
+            }
+        );
+        let engage: EstopCommand = serde_json::from_str(r#"{"action": "engage"}"#).unwrap();
+        assert_eq!(
+            engage,
+            EstopCommand::Engage {
+                disable_torque: false,
+                release_arm: false,
+            }
+        );
+        let reset: EstopCommand = serde_json::from_str(r#"{"action": "reset"}"#).unwrap();
+        assert_eq!(reset, EstopCommand::Reset);
+    }
+
+    #[test]
+    fn test_mux_source_in_health() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        let teleop = runtime
+            .mux
+            .sources()
+            .position(|s| s.name == "teleop")
+            .unwrap();
+        runtime.on_command(
+            0,
+            BaseCommand {
+                x_vel: 0.1,
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        runtime.on_command(
+            teleop,
+            BaseCommand {
+                x_vel: -0.1,
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+
+        assert_eq!(runtime.compute_target(Instant::now()).x_vel, -0.1);
+        let health = runtime.health();
+        assert!(health.is_ok());
+        assert_eq!(health.active_source.as_deref(), Some("teleop"));
+    }
+
+    #[test]
+    fn test_info_reports_motor_state_and_last_error() {
+        let mut runtime = sim_runtime([7, 8, 10]);
+        let info = runtime.info();
+        assert_eq!(info.motors, MotorState::Uninitialized);
+        assert_eq!(info.config["motor"]["ids"], serde_json::json!([7, 8, 10]));
+        assert_eq!(info.build.version, env!("CARGO_PKG_VERSION"));
+        assert!(info.last_error.is_none());
+
+        assert!(runtime.init_motors().is_err());
+        let info = runtime.info();
+        assert_eq!(info.motors, MotorState::Offline { attempts: 1 });
+        assert!(info.last_error.unwrap().message.contains("10"));
+
+        // The last error survives recovery
+        runtime.config.motor.ids = [7, 8, 9];
+        runtime.init_motors().unwrap();
+        let info = runtime.info();
+        assert_eq!(info.motors, MotorState::Ready);
+        assert!(info.last_error.is_some());
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""motors":{"state":"ready"}"#));
+        let parsed: RuntimeInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, info);
+    }
+
+    #[test]
+    fn test_param_set_applies_next_tick() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        let set = |name: &str, value| ParamRequest::Set {
+            name: name.to_string(),
+            value,
+        };
+
+        let reply = runtime
+            .on_param_request(set("limits.max_x_vel", serde_json::json!(0.2)))
+            .unwrap();
+        assert_eq!(reply[0].value, serde_json::json!(0.2));
+        runtime
+            .on_param_request(set("cmd_timeout_ms", serde_json::json!(100)))
+            .unwrap();
+        assert!(
+            runtime
+                .on_param_request(set("limits.max_x_vel", serde_json::json!(2.0)))
+                .is_err()
+        );
+
+        // Staged, not applied yet
+        assert_eq!(runtime.config.limits.max_x_vel, 0.5);
+        let list = runtime.on_param_request(ParamRequest::List).unwrap();
+        assert!(
+            list.iter()
+                .any(|p| p.name == "cmd_timeout_ms" && p.value == 100)
+        );
+
+        let changes = runtime.apply_params();
+        assert_eq!(
+            changes.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["limits.max_x_vel", "cmd_timeout_ms"]
+        );
+        assert_eq!(changes[1].old, serde_json::json!(250));
+        assert!(runtime.apply_params().is_empty());
+
+        let default = runtime.mux.source_index(DEFAULT_SOURCE).unwrap();
+        let t0 = Instant::now();
+        runtime.on_command(
+            default,
+            BaseCommand {
+                x_vel: 0.4,
+                ..Default::default()
+            },
+            t0,
+        );
+        assert_eq!(runtime.compute_target(t0).x_vel, 0.2);
+        // Stale once the new 100 ms timeout has passed
+        let later = t0 + Duration::from_millis(120);
+        assert_eq!(runtime.compute_target(later).x_vel, 0.0);
+    }
+
+    #[test]
+    fn test_sim_mode_drives_simulated_base() {
+        let mut config = RuntimeConfig::default();
+        config.motor.enabled = false;
+        config.motor.port = "/nonexistent/tty".to_string();
+        config.sim.enabled = true;
+        config.arm.enabled = true; // the simulated bus carries the arm too
+        let mut runtime = Runtime::new(config);
+        runtime.init_motors().unwrap();
+        assert_eq!(runtime.info().motors, MotorState::Ready);
+        runtime.sim_pose();
+
+        let turn = BaseActuation {
+            x_vel: 0.0,
+            y_vel: 0.0,
+            theta_vel: 90.0,
+        };
+        runtime.send_to_motors(&turn);
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        let kinematics = runtime.config.kinematics;
+        let sim = runtime.sim.as_mut().unwrap();
+        let pose = sim.step(Duration::from_millis(200), &kinematics);
+        assert!(pose.theta > 0.0, "{:?}", pose);
+
+        runtime.send_to_motors(&turn);
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        assert!(runtime.take_readings().unwrap().wheels[0].velocity != 0);
+    }
+
+    #[test]
+    fn test_arm_follows_commands_until_estop() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        runtime.init_motors().unwrap();
+        let goal = ArmJoints {
+            elbow_flex: 45.0,
+            gripper: -10.0,
+            ..Default::default()
+        };
+        runtime.on_arm_command(ArmCommand { positions: goal });
+        cycle(&mut runtime);
+        let kinematics = runtime.config.kinematics;
+        let sim = runtime.sim.as_mut().unwrap();
+        sim.step(Duration::from_millis(200), &kinematics);
+        cycle(&mut runtime);
+        let state = Runtime::arm_state(&runtime.take_readings().unwrap()).unwrap();
+        assert!(
+            (state.position.elbow_flex - 45.0).abs() < 0.1,
+            "{:?}",
+            state
+        );
+        assert!((state.position.gripper + 10.0).abs() < 0.1, "{:?}", state);
+
+        // Non-finite joints reject the whole command
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            runtime.on_arm_command(ArmCommand {
+                positions: ArmJoints {
+                    wrist_roll: bad,
+                    ..Default::default()
+                },
+            });
+            assert_eq!(runtime.arm_goal, Some(goal));
+        }
+
+        // Latched estop: the arm holds, commands are ignored
+        runtime.on_estop(EstopCommand::Engage {
+            disable_torque: true,
+            release_arm: false,
+        });
+        runtime.on_arm_command(ArmCommand {
+            positions: ArmJoints::default(),
+        });
+        assert_eq!(runtime.arm_goal, None);
+        cycle(&mut runtime);
+        let bank = runtime.sim.as_ref().unwrap().bank().clone();
+        assert_eq!(bank.peek(7, Register::TorqueEnable), Some(0));
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(1));
+
+        // Only released on request
+        runtime.on_estop(EstopCommand::Engage {
+            disable_torque: true,
+            release_arm: true,
+        });
+        cycle(&mut runtime);
+        assert_eq!(bank.peek(1, Register::TorqueEnable), Some(0));
+    }
+
+    #[test]
+    fn test_scan_reports_servos_on_the_bus() {
+        let mut runtime = sim_runtime([7, 8, 9]);
+        assert!(runtime.request_scan(ScanRequest::default()).is_err()); // not connected
+        runtime.init_motors().unwrap();
+
+        let request = ScanRequest {
+            baud_rates: Some(vec![9600]),
+        };
+        assert!(runtime.request_scan(request).unwrap_err().contains("9600"));
+
+        // Only with the base stopped by the estop
+        let request = ScanRequest {
+            baud_rates: Some(vec![1_000_000]),
+        };
+        assert!(runtime.request_scan(request.clone()).is_err());
+        runtime.on_estop(EstopCommand::Engage {
+            disable_torque: false,
+            release_arm: false,
+        });
+
+        runtime.request_scan(request.clone()).unwrap();
+        assert!(runtime.request_scan(request).is_err()); // one at a time
+        assert!(runtime.sync_motors(INIT_TIMEOUT));
+        let inventory = runtime.take_scan_result().unwrap().unwrap();
+        let ids: Vec<u8> = inventory.servos.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        // The bus is still usable after a scan
+        cycle(&mut runtime);
+        assert!(runtime.take_readings().is_some());
+    }
+
+    #[test]
+    fn test_stalled_motor_io_shows_in_health() {
+        let bank = SimServoBank::new(&[7, 8, 9]);
+        let mut runtime = runtime_on(&bank);
+        runtime.motor_reported_at = Instant::now() - runtime.config.loop_period() * 20;
+        assert!(matches!(
+            runtime.health().reasons[..],
+            [HealthReason::CmdStale, HealthReason::MotorIoStalled { .. }]
+        ));
+        cycle(&mut runtime);
+        assert_eq!(runtime.health().reasons, vec![HealthReason::CmdStale]);
+    }
+}
@@ -0,0 +1,163 @@
+// Kinematic simulation of the LeKiwi base
+//
+// `--sim` replaces the serial port with simulated wheel servos that follow
+// their velocity goals with lag and saturation, and report noisy velocities.
+// The runtime drives them exactly like real motors, so wheel telemetry and
+// odometry come out of the normal feedback path. The ground-truth pose is
+// integrated separately, from the simulator's exact wheel velocities at a
+// fine time step, to compare odometry (and anything built on it) against.
+//
+// The simulated base owns the servos' clock: they only move as far as
+// `update` (wall-clock time) or `step` (explicit time) advance it.
+
+use std::time::{Duration, Instant};
+
+use crate::config::SimConfig;
+use crate::messages::BaseOdometry;
+use crate::motor::feetech::FeetechBus;
+use crate::motor::kinematics::{BodyVelocity, KinematicParams, wheel_steps_to_body_with_params};
+use crate::motor::sim::{SimDynamics, SimServoBank};
+
+/// Time step of the ground-truth integration
+const TRUTH_STEP: Duration = Duration::from_millis(1);
+
+/// Simulated base: wheel servos plus the ground-truth pose
+pub struct SimBase {
+    bank: SimServoBank,
+    ids: [u8; 3], // [left, back, right]
+    pose: TruePose,
+    updated_at: Option<Instant>,
+}
+
+impl SimBase {
+    /// `arm_ids` are extra position-mode servos on the same bus
+    pub fn new(config: &SimConfig, ids: [u8; 3], arm_ids: &[u8]) -> Self {
+        let all_ids: Vec<u8> = ids.iter().chain(arm_ids).copied().collect();
+        let bank = SimServoBank::new(&all_ids);
+        bank.advance_clock(Duration::ZERO); // from now on the servos move with `step`
+        bank.set_dynamics(SimDynamics {
+            lag: config.lag_ms as f64 / 1000.0,
+            max_velocity: config.max_velocity as f64,
+            noise: config.noise as f64,
+        });
+        Self {
+            bank,
+            ids,
+            pose: TruePose::default(),
+            updated_at: None,
+        }
+    }
+
+    /// A new bus connected to the simulated servos
+    pub fn bus(&self) -> FeetechBus {
+        FeetechBus::new(Box::new(self.bank.transport()))
+    }
+
+    /// Simulated servos, e.g. to inspect register state
+    pub fn bank(&self) -> &SimServoBank {
+        &self.bank
+    }
+
+    /// Advance the simulation to the current time and return the ground-truth pose
+    pub fn update(&mut self, kinematics: &KinematicParams) -> BaseOdometry {
+        let now = Instant::now();
+        let dt = self
+            .updated_at
+            .map_or(Duration::ZERO, |t| now.duration_since(t));
+        self.updated_at = Some(now);
+        self.step(dt, kinematics)
+    }
+
+    /// Advance the simulation by `dt` and return the ground-truth pose
+    pub fn step(&mut self, dt: Duration, kinematics: &KinematicParams) -> BaseOdometry {
+        let mut remaining = dt;
+        loop {
+            let h = remaining.min(TRUTH_STEP);
+            self.bank.advance_clock(h);
+            let velocities = self
+                .ids
+                .map(|id| self.bank.true_velocity(id).unwrap_or(0.0) as f32);
+            let twist = wheel_steps_to_body_with_params(
+                velocities,
+                kinematics.wheel_radius,
+                kinematics.base_radius,
+            );
+            self.pose.integrate(twist, h.as_secs_f64());
+            remaining -= h;
+            if remaining.is_zero() {
+                break;
+            }
+        }
+        self.pose.message()
+    }
+}
+
+/// Exact pose of the simulated base
+#[derive(Debug, Clone, Copy, Default)]
+struct TruePose {
+    x: f64,     // m
+    y: f64,     // m
+    theta: f64, // rad, unwrapped
+    twist: BodyVelocity,
+}
+
+impl TruePose {
+    /// Move along `twist` for `dt` seconds, using the midpoint heading
+    fn integrate(&mut self, twist: BodyVelocity, dt: f64) {
+        let omega = (twist.theta as f64).to_radians();
+        let (sin, cos) = (self.theta + omega * dt / 2.0).sin_cos();
+        self.x += (twist.x as f64 * cos - twist.y as f64 * sin) * dt;
+        self.y += (twist.x as f64 * sin + twist.y as f64 * cos) * dt;
+        self.theta += omega * dt;
+        self.twist = twist;
+    }
+
+    fn message(&self) -> BaseOdometry {
+        // Heading wrapped to (-180, 180] like the odometry pose
+        let theta = self.theta.to_degrees().rem_euclid(360.0);
+        BaseOdometry {
+            x: self.x as f32,
+            y: self.y as f32,
+            theta: if theta > 180.0 { theta - 360.0 } else { theta } as f32,
+            x_vel: self.twist.x,
+            y_vel: self.twist.y,
+            theta_vel: self.twist.theta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::{BASE_MOTOR_IDS, MotorDriver};
+
+    /// Drive forward at 0.2 m/s for 200 ms
+    fn drive(config: &SimConfig) -> BaseOdometry {
+        let kinematics = KinematicParams::default();
+        let mut sim = SimBase::new(config, BASE_MOTOR_IDS, &[]);
+        let mut driver = MotorDriver::with_bus(sim.bus(), BASE_MOTOR_IDS);
+        driver.initialize().unwrap();
+        driver.set_body_velocity(0.2, 0.0, 0.0).unwrap();
+        sim.step(Duration::from_millis(200), &kinematics)
+    }
+
+    #[test]
+    fn test_ground_truth_follows_commands() {
+        let config = SimConfig {
+            lag_ms: 20,
+            ..SimConfig::default()
+        };
+        let pose = drive(&config);
+        assert!((pose.x_vel - 0.2).abs() < 0.001, "{:?}", pose);
+        // First-order lag: one time constant behind an ideal base, 0.2 * (0.2 - 0.02) m
+        assert!((pose.x - 0.036).abs() < 0.0005, "{:?}", pose);
+        assert!(pose.y.abs() < 1e-4 && pose.theta.abs() < 0.01, "{:?}", pose);
+
+        // Measurement noise only affects the feedback, not the true motion
+        let noisy = drive(&SimConfig {
+            noise: 50.0,
+            ..config
+        });
+        assert_eq!((noisy.x, noisy.y), (pose.x, pose.y));
+    }
+}